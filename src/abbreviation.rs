@@ -0,0 +1,139 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+/// How far a label is shortened to make it fit, from not at all to a few letters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Stage {
+    #[default]
+    Full,
+    /// With the words in the dictionary of abbreviations replaced
+    Dictionary,
+    /// Without vowels as well, except at the start of a word
+    Vowels,
+    /// Cut off after a few letters
+    Ellipsis,
+}
+
+impl Stage {
+    const ALL: [Stage; 4] = [
+        Stage::Full,
+        Stage::Dictionary,
+        Stage::Vowels,
+        Stage::Ellipsis,
+    ];
+}
+
+/// The letters a label keeps before the ellipsis
+const KEEP: usize = 5;
+
+/// A label shortened up to a stage, every stage on top of the ones before it. The dictionary
+/// replaces whole words and phrases as they are written, longer ones first.
+pub(crate) fn shorten(text: &str, stage: Stage, dictionary: &BTreeMap<String, String>) -> String {
+    let mut short = text.to_string();
+    if stage >= Stage::Dictionary {
+        let phrases = dictionary
+            .iter()
+            .sorted_by_key(|(long, _)| Reverse(long.chars().count()));
+        for (long, abbreviation) in phrases {
+            short = replace_words(&short, long, abbreviation);
+        }
+    }
+    if stage >= Stage::Vowels {
+        let mut previous = ' ';
+        short.retain(|c| {
+            let keep = !"aeiou".contains(c) || !previous.is_alphabetic();
+            previous = c;
+            keep
+        });
+    }
+    if stage >= Stage::Ellipsis && short.chars().count() > KEEP + 1 {
+        short = short.chars().take(KEEP).chain(['…']).collect();
+    }
+    short
+}
+
+/// Replaces a phrase where it stands as words of its own, not inside a longer word.
+fn replace_words(text: &str, phrase: &str, replacement: &str) -> String {
+    let mut replaced = String::new();
+    let mut rest = 0;
+    for (start, _) in text.match_indices(phrase) {
+        let end = start + phrase.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if [before, after]
+            .into_iter()
+            .flatten()
+            .any(char::is_alphanumeric)
+        {
+            continue;
+        }
+        replaced.push_str(&text[rest..start]);
+        replaced.push_str(replacement);
+        rest = end;
+    }
+    replaced.push_str(&text[rest..]);
+    replaced
+}
+
+/// The next stage that makes a label any shorter, if there is one.
+pub(crate) fn next(
+    text: &str,
+    stage: Stage,
+    dictionary: &BTreeMap<String, String>,
+) -> Option<Stage> {
+    let length = shorten(text, stage, dictionary).chars().count();
+    Stage::ALL
+        .into_iter()
+        .filter(|next| *next > stage)
+        .find(|next| shorten(text, *next, dictionary).chars().count() < length)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::abbreviation::{next, shorten, Stage};
+
+    #[test]
+    fn test_shorten() {
+        let dictionary = BTreeMap::from([
+            ("Access Point".to_string(), "AP".to_string()),
+            ("Access".to_string(), "Acc".to_string()),
+            ("Television".to_string(), "TV".to_string()),
+        ]);
+        let shorten = |text, stage| shorten(text, stage, &dictionary);
+        assert_eq!(
+            "Access Point Hall",
+            shorten("Access Point Hall", Stage::Full)
+        );
+        assert_eq!("AP Hall", shorten("Access Point Hall", Stage::Dictionary));
+        assert_eq!("AP Hll", shorten("Access Point Hall", Stage::Vowels));
+        assert_eq!(
+            "AP Hl…",
+            shorten("Access Point Hall Upstairs", Stage::Ellipsis)
+        );
+        assert_eq!("Drbll", shorten("Doorbell", Stage::Vowels));
+        // Only whole words are replaced, not the start of a longer one
+        assert_eq!(
+            "Accessories, Acc",
+            shorten("Accessories, Access", Stage::Dictionary)
+        );
+        assert_eq!(
+            Some(Stage::Vowels),
+            next("Accessories", Stage::Full, &dictionary)
+        );
+
+        // Television is as short as it gets once it is TV
+        assert_eq!(
+            Some(Stage::Dictionary),
+            next("Television", Stage::Full, &dictionary)
+        );
+        assert_eq!(None, next("Television", Stage::Dictionary, &dictionary));
+        assert_eq!(
+            Some(Stage::Vowels),
+            next("Doorbell", Stage::Full, &dictionary)
+        );
+    }
+}
@@ -1,15 +1,44 @@
 use grid::Grid;
 use std::ops::Add;
 
-use crate::placeholder::{Color, PlaceHolder};
+use crate::placeholder::{Color, PlaceHolder, Style};
 use crate::round::Round;
 use crate::template::Template;
 
+/// The outline of a label that is placed in rows, see
+/// [`LabelStyle`](crate::panel::LabelStyle).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Shape {
+    /// Text in a box
+    Boxed,
+    /// Text with a leader to the arrow
+    Compact,
+}
+
+impl Shape {
+    /// The lines a row of labels takes.
+    pub(crate) fn lines(&self) -> usize {
+        match self {
+            Shape::Boxed => 3,
+            Shape::Compact => 1,
+        }
+    }
+
+    /// How far left the label of a port can go.
+    pub(crate) fn max_shift(&self, text: &str, slot: usize) -> usize {
+        match self {
+            Shape::Boxed => max_shift(text, slot),
+            Shape::Compact => compact_max_shift(text, slot),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq)]
 pub(crate) struct ProtoBalloon {
-    color: Color,
+    style: Style,
     text: String,
     slot: usize,
+    shape: Shape,
 }
 
 #[derive(PartialEq, Eq)]
@@ -25,7 +54,11 @@ impl ProtoBalloon {
     }
 
     pub(crate) fn width(&self) -> usize {
-        width(&self.text)
+        match self.shape {
+            Shape::Boxed => width(&self.text),
+            // The text, a space, the leader and the two columns of the arrow
+            Shape::Compact => self.inner_width() + 4,
+        }
     }
 
     fn padding(&self) -> usize {
@@ -41,14 +74,19 @@ impl ProtoBalloon {
     }
 
     pub(crate) fn arrow(&self) -> usize {
-        self.slot * 9 + 16
+        arrow(self.slot)
     }
 
     pub(crate) fn max_shift(&self) -> usize {
-        max_shift(&self.text, self.slot)
+        self.shape.max_shift(&self.text, self.slot)
     }
 }
 
+/// The left column of the arrow to the jack of a slot.
+pub(crate) fn arrow(slot: usize) -> usize {
+    slot * 9 + 16
+}
+
 pub(crate) fn inner_width(text: &str) -> usize {
     text.chars().count()
 }
@@ -57,8 +95,33 @@ pub(crate) fn width(text: &str) -> usize {
     inner_width(text).up(3).add(4)
 }
 
+/// Shifting left by a slot moves the balloon 9 columns, and the two columns of the arrow must stay
+/// above its bottom edge, short of the corner.
 pub(crate) fn max_shift(text: &str, slot: usize) -> usize {
-    slot.add(1).min(width(text) / 9)
+    slot.add(1).min((width(text) - 3) / 9)
+}
+
+/// A compact label goes to the right of its arrow, or with a shift of one to the left of it, as long
+/// as it doesn't stick out on the left.
+pub(crate) fn compact_max_shift(text: &str, slot: usize) -> usize {
+    usize::from(inner_width(text) + 2 <= arrow(slot))
+}
+
+/// Writes a label top to bottom in the two columns of the arrow of its slot, with its last line
+/// right above the jack. A label longer than the height goes on in the second column, and one that
+/// doesn't fit in both ends in an ellipsis.
+pub(crate) fn upright(style: Style, text: &str, slot: usize, height: usize) -> Template {
+    let mut chars: Vec<char> = text.chars().collect();
+    if chars.len() > 2 * height {
+        chars.truncate(2 * height - 1);
+        chars.push('…');
+    }
+    let lines = chars.len().min(height);
+    let mut grid = Grid::init(lines, arrow(slot) + 2, PlaceHolder::None);
+    for (index, c) in chars.into_iter().enumerate() {
+        grid[index % height][arrow(slot) + index / height] = PlaceHolder::Text(c, style);
+    }
+    Template::new(grid)
 }
 
 impl Balloon {
@@ -81,14 +144,56 @@ impl Balloon {
         slot: usize,
         row: usize,
         shift: usize,
+    ) -> Result<Balloon, String> {
+        Self::build(Shape::Boxed, color, text, slot, row, shift)
+    }
+
+    /// A label of a single line, see [`Shape::Compact`]. A shift of one puts it on the left.
+    pub(crate) fn compact(
+        color: Color,
+        text: String,
+        slot: usize,
+        row: usize,
+        shift: usize,
+    ) -> Result<Balloon, String> {
+        Self::build(Shape::Compact, color, text, slot, row, shift)
+    }
+
+    fn build(
+        shape: Shape,
+        color: Color,
+        text: String,
+        slot: usize,
+        row: usize,
+        shift: usize,
     ) -> Result<Balloon, String> {
         if slot > Self::RANGE {
             return Err(format!("{} is not in a valid slot!", slot));
         }
-        Self::from_proto(ProtoBalloon { color, text, slot }, row, shift)
+        Self::from_proto(
+            ProtoBalloon {
+                style: color.into(),
+                text,
+                slot,
+                shape,
+            },
+            row,
+            shift,
+        )
     }
 
-    pub fn _right(color: Color, text: String, slot: usize, row: usize) -> Result<Balloon, String> {
+    /// Draws the balloon with another tone or emphasis, like dimmed for reserved ports.
+    pub(crate) fn with_style(mut self, style: Style) -> Balloon {
+        self.proto.style = style;
+        self
+    }
+
+    pub(crate) fn right(
+        color: Color,
+        text: String,
+        slot: usize,
+        row: usize,
+    ) -> Result<Balloon, String> {
         Self::new(color, text, slot, row, 0)
     }
 
@@ -102,16 +207,38 @@ impl Balloon {
         Self::new(color, text, slot, row, shift)
     }
 
+    /// The port the arrow points at.
+    pub(crate) fn slot(&self) -> usize {
+        self.proto.slot
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.proto.text
+    }
+
+    pub(crate) fn shift(&self) -> usize {
+        self.shift
+    }
+
+    /// The left column of the arrow.
+    pub(crate) fn arrow(&self) -> usize {
+        self.proto.arrow()
+    }
+
     pub(crate) fn x(&self) -> usize {
-        self.proto.arrow() - self.shift * 9 - 1
+        match (self.proto.shape, self.shift) {
+            (Shape::Boxed, shift) => self.proto.arrow() - shift * 9 - 1,
+            (Shape::Compact, 0) => self.proto.arrow(),
+            (Shape::Compact, _) => self.proto.arrow() - self.proto.inner_width() - 2,
+        }
     }
 
     pub(crate) fn y(&self) -> usize {
-        self.row * 3
+        self.row * self.proto.shape.lines()
     }
 
     pub(crate) fn _height(&self) -> usize {
-        self.y() + 3
+        self.y() + self.proto.shape.lines()
     }
 
     pub(crate) fn start(&self) -> usize {
@@ -122,6 +249,34 @@ impl Balloon {
         self.x() + self.proto.width()
     }
 
+    /// Whether an arrow with its left column here would go through this balloon.
+    pub(crate) fn covers(&self, arrow: usize) -> bool {
+        self.start() <= arrow + 1 && arrow < self.end()
+    }
+
+    /// Whether an arrow with its left column here still shows where it goes through this balloon:
+    /// across its borders, its leader and its padding, but not its text or its sides.
+    pub(crate) fn crossable(&self, arrow: usize) -> bool {
+        let grid = match self.proto.shape {
+            Shape::Boxed => self.pre_render_balloon(),
+            Shape::Compact => self.pre_render_compact(),
+        };
+        (0..grid.rows()).all(|row| {
+            (arrow..arrow + 2).all(|col| {
+                matches!(
+                    grid.get(row, col),
+                    None | Some(
+                        PlaceHolder::None
+                            | PlaceHolder::Padding
+                            | PlaceHolder::North(_)
+                            | PlaceHolder::South(_)
+                            | PlaceHolder::Leader(_)
+                    )
+                )
+            })
+        })
+    }
+
     pub(crate) fn overlaps(&self, other: &Self) -> bool {
         let (left, right) = if self.x() < other.x() {
             (self, other)
@@ -132,16 +287,72 @@ impl Balloon {
     }
 
     pub fn pre_render(&self) -> Template {
+        let mut grid = match self.proto.shape {
+            Shape::Boxed => self.pre_render_balloon(),
+            Shape::Compact => self.pre_render_compact(),
+        };
+        let arrow = self.proto.arrow();
+        [[PlaceHolder::None]
+            .repeat(arrow)
+            .into_iter()
+            .chain([
+                PlaceHolder::ArrowLeft(self.proto.style),
+                PlaceHolder::ArrowRight(self.proto.style),
+            ])
+            .chain([PlaceHolder::None].repeat(self.end() - arrow - 2))
+            .collect()]
+        .into_iter()
+        .cycle()
+        .take(self.y())
+        .for_each(|row| grid.push_row(row));
+
+        Template::new(grid)
+    }
+
+    /// A single line: the text and a leader to the top of the arrow, on the side of the shift.
+    fn pre_render_compact(&self) -> Grid<PlaceHolder> {
+        let style = self.proto.style;
+        let text = self.proto.text.chars().map(|c| PlaceHolder::Text(c, style));
+        let leader = [PlaceHolder::Padding, PlaceHolder::Leader(style)];
+        let line: Vec<PlaceHolder> = match self.shift {
+            0 => [
+                PlaceHolder::NorthWest(style),
+                PlaceHolder::LeaderJoint(style),
+            ]
+            .into_iter()
+            .chain(leader.into_iter().rev())
+            .chain(text)
+            .collect(),
+            _ => text
+                .chain(leader)
+                .chain([
+                    PlaceHolder::LeaderJoint(style),
+                    PlaceHolder::NorthEast(style),
+                ])
+                .collect(),
+        };
+        Grid::from_vec(
+            [PlaceHolder::None]
+                .repeat(self.x())
+                .into_iter()
+                .chain(line)
+                .collect(),
+            self.end(),
+        )
+    }
+
+    fn pre_render_balloon(&self) -> Grid<PlaceHolder> {
         let mut grid = Grid::init(1, Self::WIDTH, PlaceHolder::None);
         let x = self.x();
+        let style = self.proto.style;
 
         grid.pop_row();
         grid.push_row(
             [
                 &[PlaceHolder::None].repeat(x)[..],
-                &[PlaceHolder::NorthWest],
-                &[PlaceHolder::North].repeat(self.proto.width() - 2)[..],
-                &[PlaceHolder::NorthEast],
+                &[PlaceHolder::NorthWest(style)],
+                &[PlaceHolder::North(style)].repeat(self.proto.width() - 2)[..],
+                &[PlaceHolder::NorthEast(style)],
             ]
             .concat(),
         );
@@ -150,25 +361,20 @@ impl Balloon {
             [PlaceHolder::None]
                 .repeat(x)
                 .into_iter()
-                .chain([PlaceHolder::West])
+                .chain([PlaceHolder::West(style)])
                 .chain([PlaceHolder::Padding].repeat(self.proto.left_padding()))
-                .chain(
-                    self.proto
-                        .text
-                        .chars()
-                        .map(|c| PlaceHolder::Text(c, self.proto.color)),
-                )
+                .chain(self.proto.text.chars().map(|c| PlaceHolder::Text(c, style)))
                 .chain([PlaceHolder::Padding].repeat(self.proto.right_padding()))
-                .chain([PlaceHolder::East])
+                .chain([PlaceHolder::East(style)])
                 .collect(),
         );
 
         grid.push_row(
             [
                 &[PlaceHolder::None].repeat(x)[..],
-                &[PlaceHolder::SouthWest],
-                &[PlaceHolder::South].repeat(self.proto.width() - 2)[..],
-                &[PlaceHolder::SouthEast],
+                &[PlaceHolder::SouthWest(style)],
+                &[PlaceHolder::South(style)].repeat(self.proto.width() - 2)[..],
+                &[PlaceHolder::SouthEast(style)],
             ]
             .concat(),
         );
@@ -176,58 +382,44 @@ impl Balloon {
         let arrow = self.proto.arrow();
 
         match grid[2][arrow] {
-            PlaceHolder::SouthWest => {
-                grid[2][arrow] = PlaceHolder::TransitionLeft;
-                grid[2][arrow + 1] = PlaceHolder::TransitionRightEdge;
+            PlaceHolder::SouthWest(_) => {
+                grid[2][arrow] = PlaceHolder::TransitionLeft(style);
+                grid[2][arrow + 1] = PlaceHolder::TransitionRightEdge(style);
             }
-            PlaceHolder::SouthEast => {
-                grid[2][arrow] = PlaceHolder::TransitionLeft;
-                grid[2][arrow + 1] = PlaceHolder::TransitionRightEdge;
+            PlaceHolder::SouthEast(_) => {
+                grid[2][arrow] = PlaceHolder::TransitionLeft(style);
+                grid[2][arrow + 1] = PlaceHolder::TransitionRightEdge(style);
             }
             _ => {
-                grid[2][arrow] = PlaceHolder::TransitionLeft;
-                grid[2][arrow + 1] = PlaceHolder::TransitionRight;
+                grid[2][arrow] = PlaceHolder::TransitionLeft(style);
+                grid[2][arrow + 1] = PlaceHolder::TransitionRight(style);
             }
         }
-
-        [[PlaceHolder::None]
-            .repeat(arrow)
-            .into_iter()
-            .chain([PlaceHolder::ArrowLeft, PlaceHolder::ArrowRight])
-            .chain([PlaceHolder::None].repeat(x + self.proto.width() - arrow - 2))
-            .collect()]
-        .into_iter()
-        .cycle()
-        .take(self.y())
-        .for_each(|row| grid.push_row(row));
-
-        Template::new(grid)
+        grid
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::balloon::{max_shift, Balloon};
+    use crate::balloon::{compact_max_shift, max_shift, upright, Balloon};
     use crate::placeholder::Color;
+    use crate::theme::GenericTheme;
 
     #[test]
     fn test_max_shift() {
-        assert_eq!(1, max_shift("a", 0));
-        assert_eq!(1, max_shift("ab", 0));
-        assert_eq!(1, max_shift("abc", 0));
-        assert_eq!(1, max_shift("abcd", 0));
-        assert_eq!(1, max_shift("a", 10));
-        assert_eq!(1, max_shift("ab", 10));
-        assert_eq!(1, max_shift("abc", 10));
-        assert_eq!(2, max_shift("abcd", 10));
-        assert_eq!(2, max_shift("abcde", 10));
-        assert_eq!(2, max_shift("abcdef", 10));
-        assert_eq!(3, max_shift("abcdefg", 10));
+        // Labels narrower than a slot and a half stay above their jack
+        assert_eq!(0, max_shift("a", 0));
+        assert_eq!(0, max_shift("abcdef", 10));
+        assert_eq!(1, max_shift("abcdefg", 10));
+        assert_eq!(1, max_shift("abcdefghijklmno", 10));
+        assert_eq!(2, max_shift("abcdefghijklmnop", 10));
+        // No further left than the first slot
+        assert_eq!(1, max_shift("abcdefghijklmnop", 0));
     }
 
     #[test]
     fn balloon_dimensions() {
-        let balloon = Balloon::_right(
+        let balloon = Balloon::right(
             Color {
                 red: 0,
                 green: 0,
@@ -243,4 +435,41 @@ mod tests {
         assert_eq!(13, balloon.start());
         assert_eq!(17, balloon.end());
     }
+
+    #[test]
+    fn test_compact() {
+        let theme = GenericTheme::ASCII.build();
+        // The lines of the label, from its first column on
+        let render = |shift| {
+            let balloon = Balloon::compact(Color::NEUTRAL, "TV".into(), 1, 1, shift).unwrap();
+            let lines: Vec<String> = balloon
+                .pre_render()
+                .render(&*theme)
+                .lines()
+                .skip(1)
+                .map(|line| line.chars().skip(balloon.start()).collect())
+                .collect();
+            (balloon.start(), balloon.end(), lines)
+        };
+        assert_eq!((25, 31, vec!["┌┬─ TV".into(), "││    ".into()]), render(0));
+        assert_eq!((21, 27, vec!["TV ─┬┐".into(), "    ││".into()]), render(1));
+        assert_eq!(0, compact_max_shift("Fifteen letters", 0));
+        assert_eq!(1, compact_max_shift("Fifteen letters", 1));
+    }
+
+    #[test]
+    fn test_upright() {
+        let theme = GenericTheme::ASCII.build();
+        let render = |text, height| {
+            upright(Color::NEUTRAL.into(), text, 0, height)
+                .render(&*theme)
+                .lines()
+                .skip(1)
+                .map(|line| line.trim().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["T", "V"], render("TV", 4));
+        assert_eq!(vec!["St", "wc", "ih"], render("Switch", 3));
+        assert_eq!(vec!["To", "r…"], render("Trolley", 2));
+    }
 }
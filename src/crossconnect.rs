@@ -0,0 +1,344 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::balloon::arrow;
+use crate::format::Format;
+use crate::inventory::Inventory;
+use crate::panel::Panel;
+use crate::placeholder::Color;
+use crate::switch::Switch;
+
+/// A patch cord from a jack of the panel to a numbered port of the switch.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Cord {
+    pub(crate) panel: usize,
+    pub(crate) switch: usize,
+    /// Like blue or red, instead of the `color` tag of the jack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) color: Option<String>,
+}
+
+/// The panel and switch files, relative to the cross-connect file, and the cords between them.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct CrossConnectInput {
+    pub(crate) panel: PathBuf,
+    pub(crate) switch: PathBuf,
+    pub(crate) cords: Vec<Cord>,
+}
+
+/// The usual colours of patch cords.
+fn named(color: &str) -> Option<Color> {
+    let (red, green, blue) = match color.to_lowercase().as_str() {
+        "blue" => (64, 128, 255),
+        "red" => (255, 0, 0),
+        "green" => (0, 255, 0),
+        "yellow" => (255, 255, 0),
+        "orange" => (255, 128, 0),
+        "purple" => (255, 0, 255),
+        "white" => (255, 255, 255),
+        "grey" | "gray" => (192, 192, 192),
+        "black" => (96, 96, 96),
+        _ => return None,
+    };
+    Some(Color { red, green, blue })
+}
+
+/// A cord laid out between the panel and the switch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Route {
+    /// The column it leaves the panel at
+    from: usize,
+    /// The column it enters the switch at
+    to: usize,
+    /// The row it runs sideways on
+    track: usize,
+    color: Color,
+}
+
+impl Route {
+    fn span(&self) -> (usize, usize) {
+        (self.from.min(self.to), self.from.max(self.to))
+    }
+
+    /// Whether two cords would run into each other sideways, on the same track.
+    fn overlaps(&self, other: &Route) -> bool {
+        let ((start, end), (other_start, other_end)) = (self.span(), other.span());
+        self.track == other.track && start <= other_end && other_start <= end
+    }
+
+    /// Whether two cords would run into each other, sideways or with one going down the column
+    /// the other comes up from.
+    fn conflicts(&self, other: &Route) -> bool {
+        self.overlaps(other)
+            || (self.from == other.to && self.track >= other.track)
+            || (self.to == other.from && self.track <= other.track)
+    }
+}
+
+/// Lays out the cords on as few tracks as possible, each on the first track where it doesn't run
+/// into a cord laid out before it. When there is no such track, cords may share a column, but never
+/// a track.
+fn route(cords: Vec<(usize, usize, Color)>) -> Vec<Route> {
+    let mut cords = cords;
+    cords.sort_by_key(|(from, to, _)| from.abs_diff(*to));
+    let mut routes: Vec<Route> = Vec::new();
+    while !cords.is_empty() {
+        // The shortest cord, unless another one leaves the panel where it enters the switch. That
+        // one has to turn higher up, so it goes first.
+        let next = cords
+            .iter()
+            .position(|(_, to, _)| cords.iter().all(|(from, _, _)| from != to))
+            .unwrap_or(0);
+        let (from, to, color) = cords.remove(next);
+        let tracks = routes.len() + 1;
+        let candidates: Vec<Route> = (0..=tracks)
+            .map(|track| Route {
+                from,
+                to,
+                track,
+                color,
+            })
+            .collect();
+        let route = candidates
+            .iter()
+            .find(|route| routes.iter().all(|other| !route.conflicts(other)))
+            .or(candidates
+                .iter()
+                .find(|route| routes.iter().all(|other| !route.overlaps(other))))
+            .cloned()
+            .expect("A new track overlaps nothing");
+        routes.push(route);
+    }
+    routes
+}
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+fn glyph(directions: u8) -> char {
+    match directions {
+        0 => ' ',
+        d if d == UP | DOWN | LEFT | RIGHT => '┼',
+        d if d == UP | DOWN | LEFT => '┤',
+        d if d == UP | DOWN | RIGHT => '├',
+        d if d == LEFT | RIGHT | DOWN => '┬',
+        d if d == LEFT | RIGHT | UP => '┴',
+        d if d == DOWN | RIGHT => '┌',
+        d if d == DOWN | LEFT => '┐',
+        d if d == UP | RIGHT => '└',
+        d if d == UP | LEFT => '┘',
+        d if d & (LEFT | RIGHT) != 0 && d & (UP | DOWN) == 0 => '─',
+        _ => '│',
+    }
+}
+
+/// Draws the cords, a line above the tracks and one below, so every cord visibly leaves the panel
+/// and enters the switch.
+fn draw(routes: &[Route]) -> Vec<String> {
+    let tracks = routes
+        .iter()
+        .map(|route| route.track + 1)
+        .max()
+        .unwrap_or(0);
+    let width = routes
+        .iter()
+        .map(|route| route.span().1 + 1)
+        .max()
+        .unwrap_or(0);
+    let height = tracks + 2;
+    let mut cells = vec![vec![(0u8, None::<Color>); width]; height];
+    for route in routes {
+        let row = route.track + 1;
+        let mut mark = |row: usize, column: usize, directions: u8| {
+            let cell = &mut cells[row][column];
+            cell.0 |= directions;
+            cell.1 = Some(route.color);
+        };
+        for above in 0..row {
+            mark(above, route.from, UP | DOWN);
+        }
+        for below in row + 1..height {
+            mark(below, route.to, UP | DOWN);
+        }
+        let (start, end) = route.span();
+        for column in start..=end {
+            let mut directions = 0;
+            if column > start {
+                directions |= LEFT;
+            }
+            if column < end {
+                directions |= RIGHT;
+            }
+            if column == route.from {
+                directions |= UP;
+            }
+            if column == route.to {
+                directions |= DOWN;
+            }
+            mark(row, column, directions);
+        }
+    }
+    cells
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(directions, color)| {
+                    let glyph = glyph(directions).to_string();
+                    match color {
+                        Some(color) => glyph.color(colored::Color::from(color)).to_string(),
+                        None => glyph,
+                    }
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// A patch panel above a switch, with the cords between them.
+pub struct CrossConnect {
+    panel: Panel,
+    switch: Switch,
+    cords: Vec<Cord>,
+}
+
+impl CrossConnect {
+    /// Opens a cross-connect file, and the panel and switch files next to it.
+    pub fn open(path: impl AsRef<Path>, format: Option<Format>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let format = format.unwrap_or(Format::detect(path));
+        let source =
+            read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+        let input: CrossConnectInput = format
+            .parse(&source)
+            .with_context(|| format!("Could not parse {} as {}", path.display(), format))?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        Self::new(
+            Inventory::open(directory.join(input.panel), None)?.panel(),
+            Switch::open(directory.join(input.switch), None)?,
+            input.cords,
+        )
+        .map_err(|error| anyhow!(error))
+    }
+
+    /// Puts a panel above a switch, with the given cords between them.
+    pub(crate) fn new(panel: Panel, switch: Switch, cords: Vec<Cord>) -> Result<Self, String> {
+        let cross_connect = Self {
+            panel,
+            switch,
+            cords,
+        };
+        cross_connect.validate()?;
+        Ok(cross_connect)
+    }
+
+    /// Checks that every cord connects existing ports, and that no port has two cords.
+    fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+        for (index, cord) in self.cords.iter().enumerate() {
+            if cord.panel >= self.panel.slots.len() {
+                problems.push(format!("Panel port {:02} doesn't exist", cord.panel));
+            }
+            if self.switch.position(cord.switch).is_none() {
+                problems.push(format!("Switch port {} doesn't exist", cord.switch));
+            }
+            if let Some(color) = &cord.color {
+                if named(color).is_none() {
+                    problems.push(format!("Unknown cord colour '{}'", color));
+                }
+            }
+            let earlier = &self.cords[..index];
+            if earlier.iter().any(|other| other.panel == cord.panel) {
+                problems.push(format!("Panel port {:02} has two cords", cord.panel));
+            }
+            if earlier.iter().any(|other| other.switch == cord.switch) {
+                problems.push(format!("Switch port {} has two cords", cord.switch));
+            }
+        }
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems.join("\n")),
+        }
+    }
+
+    /// Changes the panel, like to apply view options to it.
+    pub fn map_panel(
+        mut self,
+        change: impl Fn(Panel) -> anyhow::Result<Panel>,
+    ) -> anyhow::Result<Self> {
+        self.panel = change(self.panel)?;
+        Ok(self)
+    }
+
+    /// Draws the panel with its balloons, the cords and the front of the switch with the balloons
+    /// of its bottom ports. The balloons of the top ports would be in the way of the cords, so
+    /// their hosts are listed below instead.
+    pub fn render(&mut self) -> anyhow::Result<String> {
+        let mut cords = Vec::new();
+        for cord in &self.cords {
+            let color = cord
+                .color
+                .clone()
+                .or(self.panel.slots[cord.panel].field("color"))
+                .and_then(|color| named(&color))
+                .unwrap_or(Color::NEUTRAL);
+            let Some(position) = self.switch.position(cord.switch) else {
+                bail!("Switch port {} doesn't exist", cord.switch);
+            };
+            // Jacks and ports take the left column of their connector, bottom ports the right
+            let from = arrow(cord.panel);
+            let to = arrow(position / 2) + position % 2;
+            cords.push((from, to, color));
+        }
+        let (balloons, frame) = self.panel.render_layers();
+        let (_, front, below) = self.switch.render_layers();
+        let mut rendered = format!(
+            "{}\n{}\n{}\n{}{}",
+            balloons,
+            frame,
+            draw(&route(cords)).join("\n"),
+            front,
+            below
+        );
+        let top = self.switch.top_hosts();
+        if !top.is_empty() {
+            rendered = format!("{}\n\nTop ports: {}", rendered, top.join(", "));
+        }
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crossconnect::{draw, route, Route};
+    use crate::placeholder::Color;
+
+    #[test]
+    fn test_route() {
+        let routes = route(vec![
+            (16, 34, Color::NEUTRAL),
+            (25, 16, Color::NEUTRAL),
+            (43, 44, Color::NEUTRAL),
+        ]);
+        let tracks: Vec<(usize, usize)> = routes
+            .iter()
+            .map(|route: &Route| (route.from, route.track))
+            .collect();
+        // The cord down column 16 turns above the one coming up it
+        assert_eq!(vec![(43, 0), (16, 0), (25, 1)], tracks);
+    }
+
+    #[test]
+    fn test_draw() {
+        let routes = route(vec![(1, 4, Color::NEUTRAL), (3, 0, Color::NEUTRAL)]);
+        colored::control::set_override(false);
+        assert_eq!(vec![" │ │", " └─┼┐", "┌──┘│", "│   │"], draw(&routes));
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt::{Display, Formatter};
+
+use crate::filter::Highlight;
+use crate::panel::Panel;
+use crate::slot::Slot;
+
+/// How a port differs between two versions of an inventory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added {
+        port: usize,
+        label: String,
+    },
+    Removed {
+        port: usize,
+        label: String,
+    },
+    Relabelled {
+        port: usize,
+        from: String,
+        to: String,
+    },
+    Regrouped {
+        port: usize,
+        label: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// The same label on another port
+    Moved {
+        from: usize,
+        to: usize,
+        label: String,
+    },
+}
+
+impl Change {
+    /// The port of the new version the change shows up at.
+    pub(crate) fn port(&self) -> usize {
+        match self {
+            Change::Added { port, .. }
+            | Change::Removed { port, .. }
+            | Change::Relabelled { port, .. }
+            | Change::Regrouped { port, .. } => *port,
+            Change::Moved { to, .. } => *to,
+        }
+    }
+
+    /// What goes in the balloon of the port on the new panel.
+    pub(crate) fn caption(&self) -> String {
+        match self {
+            Change::Added { label, .. } => format!("+ {}", label),
+            Change::Removed { label, .. } => format!("- {}", label),
+            Change::Relabelled { to, .. } => format!("~ {}", to),
+            Change::Regrouped { label, .. } => format!("~ {}", label),
+            Change::Moved { label, .. } => format!("> {}", label),
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let group = |group: &Option<String>| group.clone().unwrap_or("no group".into());
+        match self {
+            Change::Added { port, label } => write!(f, "+ {:02} {}", port, label),
+            Change::Removed { port, label } => write!(f, "- {:02} {}", port, label),
+            Change::Relabelled { port, from, to } => write!(f, "~ {:02} {} -> {}", port, from, to),
+            Change::Regrouped {
+                port,
+                label,
+                from,
+                to,
+            } => write!(
+                f,
+                "~ {:02} {}: {} -> {}",
+                port,
+                label,
+                group(from),
+                group(to)
+            ),
+            Change::Moved { from, to, label } => {
+                write!(f, "> {:02} {} (from {:02})", to, label, from)
+            }
+        }
+    }
+}
+
+/// The changes between two versions of the slots of a panel, by port.
+pub struct Diff {
+    pub(crate) changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Compares the slots port by port. A label that left its port for one that didn't have it
+    /// before counts as moved, as long as there is such a port for it.
+    pub(crate) fn new(old: &[Slot], new: &[Slot]) -> Self {
+        let ports = old.len().max(new.len());
+        fn label(slots: &[Slot], port: usize) -> Option<&str> {
+            slots.get(port).and_then(Slot::label)
+        }
+        fn group(slots: &[Slot], port: usize) -> Option<&str> {
+            slots.get(port).and_then(Slot::group)
+        }
+
+        // The old and the new port of every label that moved
+        let mut moves: Vec<(usize, usize, &str)> = Vec::new();
+        for from in 0..ports {
+            let Some(moving) = label(old, from) else {
+                continue;
+            };
+            if label(new, from) == Some(moving) {
+                continue;
+            }
+            let to = (0..ports).find(|&to| {
+                moves.iter().all(|(_, other, _)| *other != to)
+                    && label(new, to) == Some(moving)
+                    && label(old, to) != Some(moving)
+            });
+            if let Some(to) = to {
+                moves.push((from, to, moving));
+            }
+        }
+        let mut changes: Vec<Change> = moves
+            .iter()
+            .map(|&(from, to, label)| Change::Moved {
+                from,
+                to,
+                label: label.into(),
+            })
+            .collect();
+        for port in 0..ports {
+            let before = label(old, port).filter(|_| moves.iter().all(|(from, ..)| *from != port));
+            let after = label(new, port).filter(|_| moves.iter().all(|(_, to, _)| *to != port));
+            let change = match (before, after) {
+                (None, None) => None,
+                (None, Some(label)) => Some(Change::Added {
+                    port,
+                    label: label.into(),
+                }),
+                (Some(label), None) => Some(Change::Removed {
+                    port,
+                    label: label.into(),
+                }),
+                (Some(from), Some(to)) if from != to => Some(Change::Relabelled {
+                    port,
+                    from: from.into(),
+                    to: to.into(),
+                }),
+                (Some(label), Some(_)) if group(old, port) != group(new, port) => {
+                    Some(Change::Regrouped {
+                        port,
+                        label: label.into(),
+                        from: group(old, port).map(String::from),
+                        to: group(new, port).map(String::from),
+                    })
+                }
+                (Some(_), Some(_)) => None,
+            };
+            changes.extend(change);
+        }
+        changes.sort_by_key(Change::port);
+        Self { changes }
+    }
+
+    /// Compares the slots of two panels.
+    pub fn between(old: &Panel, new: &Panel) -> Self {
+        Self::new(&old.slots, &new.slots)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// One line per change, without colours, like for a CI log.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "No changes\n".into();
+        }
+        self.changes
+            .iter()
+            .map(|change| format!("{}\n", change))
+            .collect()
+    }
+
+    /// Marks the changed balloons of the new panel and dims all others.
+    pub fn mark(&self, panel: &mut Panel) {
+        if self.is_empty() {
+            return;
+        }
+        for change in &self.changes {
+            panel.set_caption(change.port(), change.caption());
+        }
+        // Ports removed from the end of the panel have nothing left to highlight
+        let ports = self
+            .changes
+            .iter()
+            .map(Change::port)
+            .filter(|port| *port < panel.slots.len())
+            .collect();
+        panel
+            .set_highlight(Highlight::Ports(ports))
+            .expect("Only ports of the panel");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::diff::Diff;
+    use crate::panel::Input;
+
+    #[test]
+    fn test_diff() {
+        let old: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: Television
+                group: Living
+              - !Occupied
+                text: Printer
+                group: Office
+              - !Occupied
+                text: Doorbell
+                group: Hall
+              - !Free
+              - !Occupied
+                text: NAS
+                group: Office
+        "})
+        .unwrap();
+        let new: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: Television
+                group: Bedroom
+              - !Free
+              - !Occupied
+                text: Door bell
+                group: Hall
+              - !Occupied
+                text: NAS
+                group: Office
+              - !Occupied
+                text: Camera
+                group: Hall
+        "})
+        .unwrap();
+        assert_eq!(
+            indoc! {"
+                ~ 00 Television: Living -> Bedroom
+                - 01 Printer
+                ~ 02 Doorbell -> Door bell
+                > 03 NAS (from 04)
+                + 04 Camera
+            "},
+            Diff::new(&old.slots, &new.slots).summary()
+        );
+        assert!(Diff::new(&old.slots, &old.slots).is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::slot::Slot;
+
+/// A change to the slot assignment of a panel.
+#[derive(Clone, Debug)]
+pub enum Edit {
+    Set { port: usize, slot: Slot },
+    Free { port: usize },
+    Move { from: usize, to: usize },
+    Swap { a: usize, b: usize },
+}
+
+impl Edit {
+    /// The ports touched by this edit.
+    pub fn ports(&self) -> Vec<usize> {
+        match self {
+            Edit::Set { port, .. } | Edit::Free { port } => vec![*port],
+            Edit::Move { from, to } => vec![*from, *to],
+            Edit::Swap { a, b } => vec![*a, *b],
+        }
+    }
+
+    pub(crate) fn apply(&self, slots: &mut [Slot]) -> Result<(), String> {
+        for port in self.ports() {
+            if port >= slots.len() {
+                return Err(format!(
+                    "Port {:02} does not exist, the panel has {} ports",
+                    port,
+                    slots.len()
+                ));
+            }
+        }
+        match self {
+            Edit::Set { port, slot } => slots[*port] = slot.clone(),
+            Edit::Free { port } => slots[*port] = Slot::Free,
+            Edit::Move { from, to } => {
+                if let Slot::Free = slots[*from] {
+                    return Err(format!(
+                        "Port {:02} is free, there is nothing to move",
+                        from
+                    ));
+                }
+                if slots[*to] != Slot::Free {
+                    return Err(format!(
+                        "Port {:02} is {}, swap the ports instead",
+                        to,
+                        slots[*to].state()
+                    ));
+                }
+                slots.swap(*from, *to);
+            }
+            Edit::Swap { a, b } => slots.swap(*a, *b),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit::Edit;
+    use crate::slot::Slot;
+
+    fn occupied(text: &str) -> Slot {
+        Slot::occupied(text, "Living")
+    }
+
+    #[test]
+    fn test_move() {
+        let mut slots = vec![occupied("Television"), Slot::Free, occupied("Doorbell")];
+        Edit::Move { from: 0, to: 1 }.apply(&mut slots).unwrap();
+        assert_eq!(
+            vec![Slot::Free, occupied("Television"), occupied("Doorbell")],
+            slots
+        );
+        assert!(Edit::Move { from: 1, to: 2 }.apply(&mut slots).is_err());
+        assert!(Edit::Move { from: 0, to: 1 }.apply(&mut slots).is_err());
+        assert!(Edit::Free { port: 3 }.apply(&mut slots).is_err());
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut slots = vec![occupied("Television"), Slot::Free, occupied("Doorbell")];
+        Edit::Swap { a: 0, b: 2 }.apply(&mut slots).unwrap();
+        assert_eq!(
+            vec![occupied("Doorbell"), Slot::Free, occupied("Television")],
+            slots
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use crate::slot::Slot;
+
+/// Selects the ports of interest, by group and by tag.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// A port must be in one of these groups, unless there are none
+    pub groups: Vec<String>,
+    /// A port must match all of these `key=value` conditions
+    pub conditions: Vec<(String, String)>,
+    /// Leave out the other ports instead of dimming them
+    pub hide: bool,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.conditions.is_empty()
+    }
+
+    pub fn matches(&self, slot: &Slot) -> bool {
+        let group = self.groups.is_empty()
+            || slot
+                .group()
+                .is_some_and(|group| self.groups.iter().any(|wanted| wanted == group));
+        group
+            && self
+                .conditions
+                .iter()
+                .all(|(key, value)| slot.field(key).as_ref() == Some(value))
+    }
+
+    /// Whether a port is left out entirely.
+    pub(crate) fn hides(&self, slot: &Slot) -> bool {
+        self.hide && !self.matches(slot)
+    }
+
+    /// Whether a port is drawn, but dimmed.
+    pub(crate) fn mutes(&self, slot: &Slot) -> bool {
+        !self.hide && !self.matches(slot)
+    }
+}
+
+/// The ports to draw attention to, by group or by number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    Group(String),
+    Ports(Vec<usize>),
+}
+
+impl Highlight {
+    pub fn matches(&self, port: usize, slot: &Slot) -> bool {
+        match self {
+            Highlight::Group(group) => slot.group() == Some(group),
+            Highlight::Ports(ports) => ports.contains(&port),
+        }
+    }
+}
+
+impl FromStr for Highlight {
+    type Err = String;
+
+    /// Reads `group:` or `ports:` followed by a group or ports like `3,5-7`. Without either, a list
+    /// of ports is read as ports and anything else as a group.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        if let Some(group) = string.strip_prefix("group:") {
+            return match group.trim().is_empty() {
+                true => Err("Expected a group after group:".into()),
+                false => Ok(Highlight::Group(group.to_string())),
+            };
+        }
+        if let Some(list) = string.strip_prefix("ports:") {
+            return ports(list)
+                .unwrap_or(Err(format!("Expected ports like 3,5-7, found '{}'", list)))
+                .map(Highlight::Ports);
+        }
+        match ports(string) {
+            Some(ports) => ports.map(Highlight::Ports),
+            None if string.trim().is_empty() => Err("Expected a group or ports".into()),
+            None => Ok(Highlight::Group(string.to_string())),
+        }
+    }
+}
+
+/// Reads ports like `3,5-7`, or nothing if it isn't a list of ports.
+fn ports(list: &str) -> Option<Result<Vec<usize>, String>> {
+    let mut ports = Vec::new();
+    for part in list.split(',') {
+        let (from, to) = part.split_once('-').unwrap_or((part, part));
+        let (from, to): (usize, usize) = (from.trim().parse().ok()?, to.trim().parse().ok()?);
+        if from > to {
+            return Some(Err(format!("The ports {}-{} run backwards", from, to)));
+        }
+        ports.extend(from..=to);
+    }
+    Some(Ok(ports))
+}
+
+/// Parses a `key=value` condition.
+pub fn condition(string: &str) -> Result<(String, String), String> {
+    string
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .ok_or(format!("Expected key=value, found '{}'", string))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::filter::{condition, Filter, Highlight};
+    use crate::panel::{Input, Panel};
+    use crate::slot::Slot;
+
+    #[test]
+    fn test_matches() {
+        let mut slot = Slot::occupied("Television", "Living");
+        if let Slot::Occupied { tags, .. } = &mut slot {
+            tags.insert("vlan".into(), "20".into());
+        }
+        let filter = Filter {
+            groups: vec!["Living".into(), "Kitchen".into()],
+            conditions: vec![condition("vlan=20").unwrap()],
+            hide: false,
+        };
+        assert!(filter.matches(&slot));
+        assert!(!filter.matches(&Slot::occupied("Doorbell", "Living")));
+        assert!(!filter.matches(&Slot::Free));
+        assert!(Filter::default().matches(&Slot::Free));
+        assert!(condition("vlan").is_err());
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(Ok(Highlight::Ports(vec![3, 5, 6, 7])), "3,5-7".parse());
+        assert_eq!(Ok(Highlight::Group("Living".into())), "Living".parse());
+        assert_eq!(
+            Ok(Highlight::Group("Ground-floor".into())),
+            "Ground-floor".parse()
+        );
+        assert!("".parse::<Highlight>().is_err());
+
+        assert_eq!(Ok(Highlight::Group("2024".into())), "group:2024".parse());
+        assert_eq!(Ok(Highlight::Ports(vec![1, 2])), "ports:1-2".parse());
+        assert!("ports:Living".parse::<Highlight>().is_err());
+        assert!("7-5".parse::<Highlight>().is_err());
+
+        let input: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            slots:
+              - !Free
+              - !Free
+        "})
+        .unwrap();
+        let mut panel = Panel::from(input);
+        assert!(panel.set_highlight(Highlight::Ports(vec![1])).is_ok());
+        assert!(panel.set_highlight(Highlight::Ports(vec![2])).is_err());
+        assert!(panel
+            .set_highlight(Highlight::Group("Living".into()))
+            .is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The serialization formats an inventory can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// Picks the format from the extension of the file, falling back to YAML.
+    pub fn detect(path: &Path) -> Format {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+            .unwrap_or(Format::Yaml)
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, source: &str) -> anyhow::Result<T> {
+        Ok(match self {
+            Format::Yaml => serde_yaml::from_str(source)?,
+            Format::Json => serde_json::from_str(source)?,
+            Format::Toml => toml::from_str(source)?,
+        })
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> anyhow::Result<String> {
+        Ok(match self {
+            Format::Yaml => serde_yaml::to_string(value)?,
+            Format::Json => serde_json::to_string_pretty(value)? + "\n",
+            Format::Toml => toml::to_string(value)?,
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            _ => Err(format!(
+                "Unknown format '{}', expected yaml, json or toml",
+                string
+            )),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Yaml => write!(f, "yaml"),
+            Format::Json => write!(f, "json"),
+            Format::Toml => write!(f, "toml"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use indoc::indoc;
+
+    use crate::format::Format;
+    use crate::panel::Input;
+    use crate::slot::Slot;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::Yaml, Format::detect(Path::new("panel.yml")));
+        assert_eq!(Format::Json, Format::detect(Path::new("panel.JSON")));
+        assert_eq!(Format::Toml, Format::detect(Path::new("panel.toml")));
+        assert_eq!(Format::Yaml, Format::detect(Path::new("panel")));
+    }
+
+    #[test]
+    fn test_parse() {
+        let slots = vec![Slot::occupied("Television", "Living"), Slot::Free];
+        let json = indoc! {r#"
+            {
+              "theme": "ASCII",
+              "slots": [
+                { "Occupied": { "text": "Television", "group": "Living" } },
+                "Free"
+              ]
+            }
+        "#};
+        let toml = indoc! {r#"
+            theme = "ASCII"
+            slots = [
+              { Occupied = { text = "Television", group = "Living" } },
+              "Free",
+            ]
+        "#};
+        assert_eq!(slots, Format::Json.parse::<Input>(json).unwrap().slots);
+        assert_eq!(slots, Format::Toml.parse::<Input>(toml).unwrap().slots);
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::{anyhow, bail};
+
+use crate::balloon::Balloon;
+use crate::panel::Input;
+use crate::slot::Slot;
+use crate::theme::GenericTheme;
+
+/// The names of the spreadsheet columns that hold the port number, the label and the group.
+#[derive(Clone, Debug)]
+pub struct Columns {
+    pub port: String,
+    pub label: String,
+    pub group: String,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            port: "port".into(),
+            label: "label".into(),
+            group: "room".into(),
+        }
+    }
+}
+
+/// The default number of ports of an imported panel.
+pub const PORTS: usize = Balloon::RANGE + 1;
+
+/// Builds an inventory from a CSV export of port assignments.
+///
+/// Rows without a label, and ports that don't appear at all, become free slots. Every duplicate
+/// port, port beyond the panel size or otherwise unusable row is reported at once.
+pub fn from_csv(
+    source: &str,
+    columns: &Columns,
+    ports: usize,
+    theme: GenericTheme,
+) -> anyhow::Result<Input> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(source.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or(anyhow!("Missing column '{}'", name))
+    };
+    let (port_column, label_column, group_column) = (
+        column(&columns.port)?,
+        column(&columns.label)?,
+        column(&columns.group)?,
+    );
+
+    let mut slots: Vec<Option<Slot>> = vec![None; ports];
+    let mut problems = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        // Positions point at the blank lines skipped before a record, which aren't counted as lines
+        let line = record.position().map_or(0, |position| {
+            let rest = &source[position.byte() as usize..];
+            let start = source.len() - rest.trim_start_matches(['\r', '\n']).len();
+            source[..start].matches('\n').count() + 1
+        });
+        let field = |column: usize| record.get(column).unwrap_or_default();
+        let (port, label, group) = (field(port_column), field(label_column), field(group_column));
+        if port.is_empty() && label.is_empty() && group.is_empty() {
+            continue;
+        }
+
+        let port: usize = match port.parse() {
+            Ok(port) => port,
+            Err(_) => {
+                problems.push(format!("Line {}: '{}' is not a port number", line, port));
+                continue;
+            }
+        };
+        let slot = if label.is_empty() {
+            Slot::Free
+        } else if group.is_empty() {
+            problems.push(format!(
+                "Line {}: port {:02} has no {}",
+                line, port, columns.group
+            ));
+            continue;
+        } else {
+            Slot::occupied(label, group)
+        };
+
+        match slots.get_mut(port) {
+            None => problems.push(format!(
+                "Line {}: port {:02} is beyond the {} ports of the panel",
+                line, port, ports
+            )),
+            Some(Some(_)) => problems.push(format!(
+                "Line {}: port {:02} is listed more than once",
+                line, port
+            )),
+            Some(empty) => *empty = Some(slot),
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(problems.join("\n"));
+    }
+    let input = Input {
+        theme,
+        metadata: Default::default(),
+        legend: false,
+        colored_outlines: false,
+        labels: Default::default(),
+        label_height: None,
+        rows: None,
+        footnotes: false,
+        abbreviate: false,
+        abbreviations: Default::default(),
+        groups: Default::default(),
+        wiring: Default::default(),
+        slots: slots
+            .into_iter()
+            .map(|slot| slot.unwrap_or(Slot::Free))
+            .collect(),
+    };
+    input.validate().map_err(|error| anyhow!(error))?;
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::import::{from_csv, Columns};
+    use crate::slot::Slot;
+    use crate::theme::GenericTheme;
+
+    #[test]
+    fn test_import() {
+        let source = indoc! {"
+            Port,Label,Room
+            2,Television,Living
+            0,,
+            1,Doorbell,Hall
+        "};
+        let input = from_csv(source, &Columns::default(), 4, GenericTheme::ASCII).unwrap();
+        assert_eq!(
+            vec![
+                Slot::Free,
+                Slot::occupied("Doorbell", "Hall"),
+                Slot::occupied("Television", "Living"),
+                Slot::Free,
+            ],
+            input.slots
+        );
+    }
+
+    #[test]
+    fn test_import_problems() {
+        let source = indoc! {"
+            jack,name,room
+            1,Television,Living
+
+            1,Doorbell,Hall
+            7,Shed,Garden
+        "};
+        let columns = Columns {
+            port: "jack".into(),
+            label: "name".into(),
+            group: "room".into(),
+        };
+        let error = from_csv(source, &columns, 4, GenericTheme::ASCII).unwrap_err();
+        assert_eq!(
+            indoc! {"
+                Line 4: port 01 is listed more than once
+                Line 5: port 07 is beyond the 4 ports of the panel"}
+            .to_string(),
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use std::fs::{read_to_string, write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use serde::Serialize;
+
+use crate::edit::Edit;
+use crate::format::Format;
+use crate::panel::{Input, Panel};
+use crate::slot::Slot;
+use crate::trace::Wiring;
+
+/// An inventory file on disk.
+///
+/// Saving only rewrites the entries of `slots` that changed, so comments and formatting elsewhere
+/// in the file survive an edit.
+pub struct Inventory {
+    path: PathBuf,
+    format: Format,
+    source: String,
+    original: Vec<Slot>,
+    input: Input,
+}
+
+impl Inventory {
+    /// Opens an inventory, in the given format or otherwise the one matching its extension.
+    pub fn open(path: impl AsRef<Path>, format: Option<Format>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let format = format.unwrap_or(Format::detect(&path));
+        let source =
+            read_to_string(&path).with_context(|| format!("Could not read {}", path.display()))?;
+        let input: Input = format
+            .parse(&source)
+            .with_context(|| format!("Could not parse {} as {}", path.display(), format))?;
+        input.validate().map_err(|error| anyhow!(error))?;
+        Ok(Self {
+            path,
+            format,
+            source,
+            original: input.slots.clone(),
+            input,
+        })
+    }
+
+    pub fn panel(&self) -> Panel {
+        self.input.clone().into()
+    }
+
+    /// Where the jacks of the panel lead.
+    pub(crate) fn wiring(&self) -> &Wiring {
+        &self.input.wiring
+    }
+
+    pub fn apply(&mut self, edit: &Edit) -> anyhow::Result<()> {
+        self.input
+            .wiring
+            .check(edit)
+            .map_err(|error| anyhow!(error))?;
+        let mut input = self.input.clone();
+        edit.apply(&mut input.slots)
+            .map_err(|error| anyhow!(error))?;
+        input.validate().map_err(|error| anyhow!(error))?;
+        self.input = input;
+        Ok(())
+    }
+
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        let patched = match self.format {
+            Format::Yaml => patch_yaml(&self.source, &self.original, &self.input.slots),
+            Format::Json => patch_json(&self.source, &self.original, &self.input.slots),
+            Format::Toml => patch_toml(&self.source, &self.original, &self.input.slots),
+        }
+        .with_context(|| format!("Could not update {}", self.path.display()))?;
+        write(&self.path, &patched)
+            .with_context(|| format!("Could not write {}", self.path.display()))?;
+        self.source = patched;
+        self.original = self.input.slots.clone();
+        Ok(())
+    }
+}
+
+/// Replaces the entries of the `slots` sequence in `source` that differ between `original` and
+/// `slots`, leaving every other line untouched.
+fn patch_yaml(source: &str, original: &[Slot], slots: &[Slot]) -> anyhow::Result<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let spans = slot_spans(&lines)?;
+    if spans.len() != original.len() || spans.len() != slots.len() {
+        bail!(
+            "Found {} entries under `slots`, expected {}",
+            spans.len(),
+            original.len()
+        );
+    }
+
+    let mut patched: Vec<String> = Vec::new();
+    let mut cursor = 0;
+    for ((span, before), after) in spans.into_iter().zip(original).zip(slots) {
+        if before == after {
+            continue;
+        }
+        let indent = lines[span.start].len() - lines[span.start].trim_start().len();
+        patched.extend(
+            lines[cursor..span.start]
+                .iter()
+                .map(|line| line.to_string()),
+        );
+        patched.push(entry(after, indent)?);
+        cursor = span.end;
+    }
+    patched.extend(lines[cursor..].iter().map(|line| line.to_string()));
+
+    let mut patched = patched.join("\n");
+    if source.ends_with('\n') {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+/// Replaces the changed entries of the `slots` array. JSON has no comments, but the order of
+/// the other keys is kept.
+fn patch_json(source: &str, original: &[Slot], slots: &[Slot]) -> anyhow::Result<String> {
+    let mut document: serde_json::Value = serde_json::from_str(source)?;
+    let array = document
+        .get_mut("slots")
+        .and_then(|slots| slots.as_array_mut())
+        .ok_or(anyhow!("Could not find `slots`"))?;
+    for (index, (before, after)) in original.iter().zip(slots).enumerate() {
+        if before != after {
+            array[index] = serde_json::to_value(after)?;
+        }
+    }
+    let mut patched = serde_json::to_string_pretty(&document)?;
+    if source.ends_with('\n') {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+/// Replaces the changed entries of the `slots` array, keeping comments and formatting.
+fn patch_toml(source: &str, original: &[Slot], slots: &[Slot]) -> anyhow::Result<String> {
+    let mut document: toml_edit::DocumentMut = source.parse()?;
+    let array = document
+        .get_mut("slots")
+        .and_then(|slots| slots.as_array_mut())
+        .ok_or(anyhow!(
+            "`slots` must be an inline array to be edited in place"
+        ))?;
+    for (index, (before, after)) in original.iter().zip(slots).enumerate() {
+        if before != after {
+            let value = after.serialize(toml_edit::ser::ValueSerializer::new())?;
+            let decor = array
+                .get(index)
+                .map(|value| value.decor().clone())
+                .unwrap_or_default();
+            array.replace(index, value).decor_mut().clone_from(&decor);
+        }
+    }
+    Ok(document.to_string())
+}
+
+/// Finds the line ranges of the entries of the top level `slots` block sequence.
+/// Comments and blank lines between entries are not part of any range.
+fn slot_spans(lines: &[&str]) -> anyhow::Result<Vec<Range<usize>>> {
+    let start = lines
+        .iter()
+        .position(|line| line.starts_with("slots:"))
+        .ok_or(anyhow!("Could not find `slots`"))?;
+    let rest = lines[start]["slots:".len()..].trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        bail!("`slots` must be a block sequence to be edited in place");
+    }
+
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut indent = None;
+    for (number, line) in lines.iter().enumerate().skip(start + 1) {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let depth = line.len() - content.len();
+        let entry = content == "-" || content.starts_with("- ");
+        match indent {
+            Some(indent) if entry && depth == indent => spans.push(number..number + 1),
+            Some(indent) if depth > indent => {
+                if let Some(span) = spans.last_mut() {
+                    span.end = number + 1;
+                }
+            }
+            None if entry => {
+                indent = Some(depth);
+                spans.push(number..number + 1);
+            }
+            _ => break,
+        }
+    }
+    Ok(spans)
+}
+
+/// Serializes a slot as an entry of the `slots` sequence at the given indentation.
+fn entry(slot: &Slot, indent: usize) -> anyhow::Result<String> {
+    let yaml = serde_yaml::to_string(slot)?;
+    // Unit variants come out as plain scalars, while inventories tag every entry
+    let yaml = if yaml.starts_with('!') {
+        yaml
+    } else {
+        format!("!{}", yaml)
+    };
+    Ok(yaml
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            if index == 0 {
+                format!("{}- {}", " ".repeat(indent), line)
+            } else {
+                format!("{}{}", " ".repeat(indent + 2), line)
+            }
+        })
+        .intersperse("\n".into())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::inventory::{patch_toml, patch_yaml};
+    use crate::panel::Input;
+    use crate::slot::Slot;
+
+    #[test]
+    fn test_patch_keeps_comments() {
+        let source = indoc! {"
+            # Ground floor
+            theme: Rounded
+            slots:
+              # Living room
+              - !Occupied
+                text: Playstation
+                group: Living
+              - !Free
+              # Kitchen
+              - !Occupied
+                text: Dinner table
+                group: Kitchen
+        "};
+        let original: Vec<Slot> = serde_yaml::from_str::<Input>(source).unwrap().slots;
+        let mut slots = original.clone();
+        slots.swap(0, 1);
+        assert_eq!(
+            indoc! {"
+                # Ground floor
+                theme: Rounded
+                slots:
+                  # Living room
+                  - !Free
+                  - !Occupied
+                    text: Playstation
+                    group: Living
+                  # Kitchen
+                  - !Occupied
+                    text: Dinner table
+                    group: Kitchen
+            "},
+            patch_yaml(source, &original, &slots).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_patch_toml_keeps_comments() {
+        let source = indoc! {r#"
+            theme = "Rounded"
+            slots = [
+              # Living room
+              { Occupied = { text = "Playstation", group = "Living" } },
+              "Free",
+            ]
+        "#};
+        let original: Vec<Slot> = toml::from_str::<Input>(source).unwrap().slots;
+        let mut slots = original.clone();
+        slots.swap(0, 1);
+        assert_eq!(
+            indoc! {r#"
+                theme = "Rounded"
+                slots = [
+                  # Living room
+                  "Free",
+                  { Occupied = { text = "Playstation", group = "Living" } },
+                ]
+            "#},
+            patch_toml(source, &original, &slots).unwrap()
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+
+use crate::panel::Panel;
+use crate::placeholder::Color;
+use crate::slot::State;
+
+/// Settings of a group of ports.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Group {
+    /// Explains the group in the legend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+}
+
+/// A line of the legend, for a group or for a state.
+pub(crate) struct Entry {
+    pub(crate) name: String,
+    pub(crate) color: Option<Color>,
+    pub(crate) ports: usize,
+    pub(crate) description: Option<String>,
+}
+
+impl Entry {
+    /// The text of the entry without the name, which themes style themselves.
+    pub(crate) fn details(&self) -> String {
+        let ports = match self.ports {
+            1 => "1 port".to_string(),
+            ports => format!("{} ports", ports),
+        };
+        match &self.description {
+            Some(description) => format!("{:>8}  {}", ports, description),
+            None => format!("{:>8}", ports),
+        }
+    }
+}
+
+/// Lists the colouring keys of the panel in the order their colours were handed out, followed by
+/// the number of ports in each state unless the panel is coloured by state already.
+pub(crate) fn entries(panel: &mut Panel) -> Vec<Entry> {
+    panel.style_keys();
+    let mut entries: Vec<Entry> = Vec::new();
+    for slot in panel.slots.clone() {
+        let Some(key) = panel.color_key(&slot) else {
+            continue;
+        };
+        match entries.iter_mut().find(|entry| entry.name == key) {
+            Some(entry) => entry.ports += 1,
+            None => entries.push(Entry {
+                color: Some(panel.theme.style_group(&key)),
+                ports: 1,
+                description: match panel.color_by.as_str() {
+                    "group" => panel
+                        .groups
+                        .get(&key)
+                        .and_then(|settings| settings.description.clone()),
+                    "state" => Some(slot.state().description().into()),
+                    _ => None,
+                },
+                name: key,
+            }),
+        }
+    }
+    if panel.color_by == "state" {
+        return entries;
+    }
+    for state in State::ALL {
+        let ports = panel
+            .slots
+            .iter()
+            .filter(|slot| slot.state() == state)
+            .count();
+        if ports > 0 {
+            entries.push(Entry {
+                name: state.to_string(),
+                color: None,
+                ports,
+                description: Some(state.description().into()),
+            });
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::legend::entries;
+    use crate::panel::{Input, Panel};
+
+    #[test]
+    fn test_entries() {
+        let mut panel: Panel = serde_yaml::from_str::<Input>(indoc! {"
+            theme: ASCII
+            groups:
+              Living:
+                description: Ground floor
+            slots:
+              - !Occupied
+                text: Doorbell
+                group: Hall
+              - !Occupied
+                text: Television
+                group: Living
+              - !Free
+              - !Planned
+                text: Playstation
+                group: Living
+        "})
+        .unwrap()
+        .into();
+        let entries = entries(&mut panel);
+        assert_eq!(
+            vec!["Hall", "Living", "occupied", "free", "planned"],
+            entries
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("  1 port", entries[0].details());
+        assert_eq!(" 2 ports  Ground floor", entries[1].details());
+        assert_eq!("  1 port  to be patched", entries[4].details());
+    }
+
+    #[test]
+    fn test_entries_by_tag() {
+        let mut panel: Panel = serde_yaml::from_str::<Input>(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: Television
+                group: Living
+                tags:
+                  vlan: '20'
+              - !Occupied
+                text: Doorbell
+                group: Hall
+                tags:
+                  vlan: '30'
+              - !Occupied
+                text: Playstation
+                group: Living
+                tags:
+                  vlan: '20'
+              - !Occupied
+                text: Shed
+                group: Garden
+        "})
+        .unwrap()
+        .into();
+        assert!(panel.set_color_by("vlna").is_err());
+        panel.set_color_by("vlan").unwrap();
+        let entries = entries(&mut panel);
+        assert_eq!(
+            vec!["20", "30", "occupied"],
+            entries
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(" 2 ports", entries[0].details());
+        assert_ne!(entries[0].color, entries[1].color);
+    }
+}
@@ -1,14 +1,28 @@
 #![feature(iter_intersperse)]
-#![feature(type_alias_impl_trait)]
-#![feature(impl_trait_in_assoc_type)]
 
+pub(crate) mod abbreviation;
 pub(crate) mod balloon;
+pub mod crossconnect;
+pub mod diff;
+pub mod edit;
+pub mod filter;
+pub mod format;
+pub mod import;
+pub mod inventory;
+pub(crate) mod legend;
+pub mod lint;
+pub mod listing;
+pub(crate) mod metadata;
 pub mod panel;
 pub(crate) mod placeholder;
+pub mod plan;
+pub mod rack;
 pub(crate) mod round;
 pub mod slot;
+pub mod switch;
 pub(crate) mod template;
 pub mod theme;
+pub mod trace;
 
 #[cfg(test)]
 mod tests {
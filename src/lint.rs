@@ -0,0 +1,420 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{Local, NaiveDate};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::panel::Panel;
+use crate::slot::Slot;
+
+/// How much a broken rule matters.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Not checked at all
+    Allow,
+    Warning,
+    /// Makes the lint command fail
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Allow => write!(f, "allow"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The checks of the lint command.
+#[derive(Clone, Copy, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// The same label twice in a group
+    DuplicateLabel,
+    /// A group with ports that aren't next to each other
+    SplitGroup,
+    /// A group of a single port
+    SinglePortGroup,
+    /// A label longer than the budget
+    LongLabel,
+    /// Spaces around a label or group
+    Whitespace,
+    /// A reservation that ran out
+    ExpiredReservation,
+    /// Groups that got the same colour, because there are more groups than colours
+    ColorCollision,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 7] = [
+        Rule::DuplicateLabel,
+        Rule::SplitGroup,
+        Rule::SinglePortGroup,
+        Rule::LongLabel,
+        Rule::Whitespace,
+        Rule::ExpiredReservation,
+        Rule::ColorCollision,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Rule::DuplicateLabel => "duplicate-label",
+            Rule::SplitGroup => "split-group",
+            Rule::SinglePortGroup => "single-port-group",
+            Rule::LongLabel => "long-label",
+            Rule::Whitespace => "whitespace",
+            Rule::ExpiredReservation => "expired-reservation",
+            Rule::ColorCollision => "color-collision",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        match self {
+            Rule::DuplicateLabel | Rule::Whitespace => Severity::Error,
+            Rule::SplitGroup
+            | Rule::SinglePortGroup
+            | Rule::LongLabel
+            | Rule::ExpiredReservation
+            | Rule::ColorCollision => Severity::Warning,
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == string.to_lowercase())
+            .ok_or(format!(
+                "Unknown rule '{}', expected one of {}",
+                string,
+                Rule::ALL.iter().map(Rule::name).join(", ")
+            ))
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The formats lint results can be printed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LintFormat {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "text" => Ok(LintFormat::Text),
+            "json" => Ok(LintFormat::Json),
+            _ => Err(format!(
+                "Unknown lint format '{}', expected text or json",
+                string
+            )),
+        }
+    }
+}
+
+impl Display for LintFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintFormat::Text => write!(f, "text"),
+            LintFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// A broken rule, at some ports.
+#[derive(Clone, Serialize, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub(crate) rule: Rule,
+    pub(crate) severity: Severity,
+    pub(crate) ports: Vec<usize>,
+    pub(crate) message: String,
+}
+
+impl Problem {
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// Which rules to check and how strictly.
+pub struct Lint {
+    severities: BTreeMap<Rule, Severity>,
+    /// The longest label that doesn't break [`Rule::LongLabel`]
+    max_label: usize,
+    /// Reservations that expire before this day broke [`Rule::ExpiredReservation`]
+    today: NaiveDate,
+}
+
+impl Default for Lint {
+    fn default() -> Self {
+        Self {
+            severities: Rule::ALL
+                .into_iter()
+                .map(|rule| (rule, rule.default_severity()))
+                .collect(),
+            max_label: Self::MAX_LABEL,
+            today: Local::now().date_naive(),
+        }
+    }
+}
+
+impl Lint {
+    /// About as long as the balloons of three neighbouring ports together
+    pub const MAX_LABEL: usize = 24;
+
+    /// Checks a rule with another severity, or not at all.
+    pub fn set_severity(&mut self, rule: Rule, severity: Severity) {
+        self.severities.insert(rule, severity);
+    }
+
+    pub fn set_max_label(&mut self, length: usize) {
+        self.max_label = length;
+    }
+
+    /// Breaks every rule the panel breaks, by port.
+    pub fn check(&self, panel: &mut Panel) -> Vec<Problem> {
+        let mut found: Vec<(Rule, Vec<usize>, String)> = Vec::new();
+        let slots = &panel.slots;
+
+        let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (port, slot) in slots.iter().enumerate() {
+            if let Some(group) = slot.group() {
+                groups.entry(group).or_default().push(port);
+            }
+        }
+        for (group, ports) in &groups {
+            let labels = ports
+                .iter()
+                .filter_map(|port| slots[*port].label().map(|label| (label, *port)))
+                .into_group_map();
+            for (label, ports) in labels.into_iter().sorted() {
+                if ports.len() > 1 {
+                    found.push((
+                        Rule::DuplicateLabel,
+                        ports,
+                        format!("'{}' appears more than once in group {}", label, group),
+                    ));
+                }
+            }
+            let runs = runs(ports);
+            if runs.len() > 1 {
+                found.push((
+                    Rule::SplitGroup,
+                    ports.clone(),
+                    format!(
+                        "Group {} is split over ports {}",
+                        group,
+                        runs.iter()
+                            .map(|(start, end)| match start == end {
+                                true => format!("{:02}", start),
+                                false => format!("{:02}-{:02}", start, end),
+                            })
+                            .join(", ")
+                    ),
+                ));
+            }
+            if ports.len() == 1 {
+                found.push((
+                    Rule::SinglePortGroup,
+                    ports.clone(),
+                    format!("Group {} has a single port", group),
+                ));
+            }
+        }
+
+        for (port, slot) in slots.iter().enumerate() {
+            if let Some(label) = slot.label() {
+                let length = label.chars().count();
+                if length > self.max_label {
+                    found.push((
+                        Rule::LongLabel,
+                        vec![port],
+                        format!(
+                            "'{}' is {} characters long, more than {}",
+                            label, length, self.max_label
+                        ),
+                    ));
+                }
+            }
+            for (kind, text) in [("Label", slot.label()), ("Group", slot.group())] {
+                if let Some(text) = text.filter(|text| text.trim() != *text) {
+                    found.push((
+                        Rule::Whitespace,
+                        vec![port],
+                        format!("{} '{}' has spaces around it", kind, text),
+                    ));
+                }
+            }
+            if let Slot::Reserved {
+                owner,
+                expiry: Some(expiry),
+                ..
+            } = slot
+            {
+                if *expiry < self.today {
+                    found.push((
+                        Rule::ExpiredReservation,
+                        vec![port],
+                        format!("The reservation for {} expired on {}", owner, expiry),
+                    ));
+                }
+            }
+        }
+
+        // Claim the colours like drawing the panel does, in port order
+        let order: Vec<(String, usize)> = groups
+            .iter()
+            .map(|(group, ports)| (group.to_string(), ports[0]))
+            .sorted_by_key(|(_, first)| *first)
+            .collect();
+        let mut colors = Vec::new();
+        for (group, _) in order {
+            let color = panel.theme.style_group(&group);
+            if let Some((other, _)) = colors.iter().find(|(_, known)| *known == color) {
+                found.push((
+                    Rule::ColorCollision,
+                    groups[group.as_str()].clone(),
+                    format!("Group {} has the same colour as group {}", group, other),
+                ));
+            }
+            colors.push((group, color));
+        }
+
+        found
+            .into_iter()
+            .map(|(rule, ports, message)| Problem {
+                rule,
+                severity: self.severities[&rule],
+                ports,
+                message,
+            })
+            .filter(|problem| problem.severity != Severity::Allow)
+            .sorted_by_key(|problem| (problem.ports.first().copied(), problem.rule))
+            .collect()
+    }
+}
+
+/// The runs of consecutive ports, as their first and last port.
+fn runs(ports: &[usize]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for port in ports.iter().copied().sorted() {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == port => *end = port,
+            _ => runs.push((port, port)),
+        }
+    }
+    runs
+}
+
+/// Prints the problems, one per line or as a JSON array.
+pub fn report(problems: &[Problem], format: LintFormat) -> anyhow::Result<String> {
+    Ok(match format {
+        LintFormat::Json => serde_json::to_string_pretty(problems)?,
+        LintFormat::Text => {
+            let count = |severity: Severity, noun: &str| {
+                let count = problems
+                    .iter()
+                    .filter(|problem| problem.severity == severity)
+                    .count();
+                match count {
+                    1 => format!("1 {}", noun),
+                    count => format!("{} {}s", count, noun),
+                }
+            };
+            problems
+                .iter()
+                .map(|problem| {
+                    format!(
+                        "{}[{}] {}: {}",
+                        problem.severity,
+                        problem.rule,
+                        problem
+                            .ports
+                            .iter()
+                            .map(|port| format!("{:02}", port))
+                            .join(","),
+                        problem.message
+                    )
+                })
+                .chain([format!(
+                    "{}, {}",
+                    count(Severity::Error, "error"),
+                    count(Severity::Warning, "warning")
+                )])
+                .join("\n")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use indoc::indoc;
+
+    use crate::lint::{report, runs, Lint, LintFormat, Rule, Severity};
+    use crate::panel::{Input, Panel};
+
+    #[test]
+    fn test_check() {
+        let input: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: NAS
+                group: Office
+              - !Occupied
+                text: 'Printer '
+                group: Hall
+              - !Occupied
+                text: NAS
+                group: Office
+              - !Reserved
+                owner: Jan
+                expiry: 2024-01-01
+        "})
+        .unwrap();
+        let mut lint = Lint {
+            today: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            ..Lint::default()
+        };
+        lint.set_severity(Rule::SinglePortGroup, Severity::Allow);
+        let problems = lint.check(&mut Panel::from(input));
+        let summary = |problems| report(problems, LintFormat::Text).unwrap();
+        assert!(summary(&problems).ends_with("\n2 errors, 2 warnings"));
+        assert!(summary(&problems[..2]).ends_with("\n1 error, 1 warning"));
+        let problems: Vec<(Rule, Severity, Vec<usize>)> = problems
+            .into_iter()
+            .map(|problem| (problem.rule, problem.severity, problem.ports))
+            .collect();
+        assert_eq!(
+            vec![
+                (Rule::DuplicateLabel, Severity::Error, vec![0, 2]),
+                (Rule::SplitGroup, Severity::Warning, vec![0, 2]),
+                (Rule::Whitespace, Severity::Error, vec![1]),
+                (Rule::ExpiredReservation, Severity::Warning, vec![3]),
+            ],
+            problems
+        );
+    }
+
+    #[test]
+    fn test_runs() {
+        assert_eq!(vec![(1, 3), (5, 5)], runs(&[5, 1, 2, 3]));
+    }
+}
@@ -0,0 +1,353 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use colored::Colorize;
+
+use crate::panel::Panel;
+use crate::placeholder::Color;
+use crate::slot::{Slot, State};
+
+/// The ways a listing can be printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Aligned columns, with groups in their colour
+    Text,
+    Markdown,
+    Csv,
+}
+
+impl FromStr for ListFormat {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "text" => Ok(ListFormat::Text),
+            "markdown" | "md" => Ok(ListFormat::Markdown),
+            "csv" => Ok(ListFormat::Csv),
+            _ => Err(format!(
+                "Unknown listing format '{}', expected text, markdown or csv",
+                string
+            )),
+        }
+    }
+}
+
+impl Display for ListFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListFormat::Text => write!(f, "text"),
+            ListFormat::Markdown => write!(f, "markdown"),
+            ListFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// The orders a listing can be sorted in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    Port,
+    /// By group, then by port. Free ports go last.
+    Group,
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "port" => Ok(SortBy::Port),
+            "group" => Ok(SortBy::Group),
+            _ => Err(format!(
+                "Unknown sort order '{}', expected port or group",
+                string
+            )),
+        }
+    }
+}
+
+impl Display for SortBy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortBy::Port => write!(f, "port"),
+            SortBy::Group => write!(f, "group"),
+        }
+    }
+}
+
+struct Row {
+    port: usize,
+    label: String,
+    group: String,
+    status: State,
+    tags: String,
+    notes: String,
+    color: Option<Color>,
+    /// Doesn't match the filter
+    muted: bool,
+}
+
+impl Row {
+    const HEADERS: [&'static str; 4] = ["Port", "Label", "Group", "Status"];
+    const EXTRA_HEADERS: [&'static str; 2] = ["Tags", "Notes"];
+
+    fn cells(&self, extras: bool) -> Vec<String> {
+        let mut cells = vec![
+            format!("{:02}", self.port),
+            self.label.clone(),
+            self.group.clone(),
+            self.status.to_string(),
+        ];
+        if extras {
+            cells.extend([self.tags.clone(), self.notes.clone()]);
+        }
+        cells
+    }
+}
+
+/// A table of the ports of a panel.
+pub struct Listing {
+    rows: Vec<Row>,
+    /// Whether any port has tags or notes, which get their own columns
+    extras: bool,
+}
+
+impl Listing {
+    /// Lists the ports of the panel, applying its filter.
+    pub fn new(panel: &mut Panel, sort: SortBy) -> Self {
+        panel.style_keys();
+        let mut rows: Vec<Row> = Vec::new();
+        for (port, slot) in panel.slots.clone().iter().enumerate() {
+            if panel.filter.hides(slot) {
+                continue;
+            }
+            let (tags, notes) = match slot {
+                Slot::Occupied { tags, notes, .. } => (
+                    tags.iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .intersperse(", ".into())
+                        .collect(),
+                    notes.clone().unwrap_or_default(),
+                ),
+                _ => Default::default(),
+            };
+            rows.push(Row {
+                port,
+                label: match slot {
+                    Slot::Reserved {
+                        owner,
+                        expiry: Some(expiry),
+                        ..
+                    } => format!("{} (until {})", owner, expiry),
+                    _ => slot.label().unwrap_or_default().to_string(),
+                },
+                group: slot.group().unwrap_or_default().to_string(),
+                status: slot.state(),
+                tags,
+                notes,
+                color: panel.color(slot),
+                muted: panel.filter.mutes(slot),
+            });
+        }
+        if let SortBy::Group = sort {
+            rows.sort_by_key(|row| (row.group.is_empty(), row.group.clone(), row.port));
+        }
+        let extras = rows
+            .iter()
+            .any(|row| !row.tags.is_empty() || !row.notes.is_empty());
+        Self { rows, extras }
+    }
+
+    pub fn render(&self, format: ListFormat) -> String {
+        match format {
+            ListFormat::Text => self.text(),
+            ListFormat::Markdown => self.markdown(),
+            ListFormat::Csv => self.csv(),
+        }
+    }
+
+    fn headers(&self) -> Vec<String> {
+        let mut headers: Vec<String> = Row::HEADERS.map(String::from).to_vec();
+        if self.extras {
+            headers.extend(Row::EXTRA_HEADERS.map(String::from));
+        }
+        headers
+    }
+
+    /// The rows to print. Formats that can't dim leave out the rows that don't match the filter.
+    fn rows(&self, dim: bool) -> Vec<&Row> {
+        self.rows.iter().filter(|row| dim || !row.muted).collect()
+    }
+
+    /// Counts the ports per state, like `3 occupied, 1 reserved`.
+    fn summary(rows: &[&Row]) -> String {
+        State::ALL
+            .iter()
+            .filter_map(|state| {
+                let count = rows.iter().filter(|row| row.status == *state).count();
+                (count > 0).then(|| format!("{} {}", count, state))
+            })
+            .intersperse(", ".into())
+            .collect()
+    }
+
+    fn widths(&self, rows: &[&Row], escape: impl Fn(&str) -> String) -> Vec<usize> {
+        let mut widths: Vec<usize> = self
+            .headers()
+            .iter()
+            .map(|header| header.chars().count())
+            .collect();
+        for row in rows {
+            for (width, cell) in widths.iter_mut().zip(row.cells(self.extras)) {
+                *width = (*width).max(escape(&cell).chars().count());
+            }
+        }
+        widths
+    }
+
+    fn text(&self) -> String {
+        let rows = self.rows(true);
+        let widths = self.widths(&rows, |cell| cell.into());
+        let line = |cells: Vec<String>, color: Option<Color>, muted: bool| {
+            cells
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (cell, width))| {
+                    // Pad before colouring, the escape codes would count towards the width
+                    let cell = format!("{:width$}", cell, width = width);
+                    let cell = match color {
+                        Some(color) if column == 1 || column == 2 => {
+                            cell.color(colored::Color::from(color))
+                        }
+                        _ => cell.normal(),
+                    };
+                    if muted {
+                        cell.dimmed().to_string()
+                    } else {
+                        cell.to_string()
+                    }
+                })
+                .intersperse("  ".into())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        [line(self.headers(), None, false)]
+            .into_iter()
+            .chain(
+                rows.iter()
+                    .map(|row| line(row.cells(self.extras), row.color, row.muted)),
+            )
+            .chain(["".into(), Self::summary(&rows)])
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    fn markdown(&self) -> String {
+        let rows = self.rows(false);
+        let escape = |cell: &str| cell.replace('|', "\\|");
+        let widths = self.widths(&rows, escape);
+        let line = |cells: Vec<String>| {
+            format!(
+                "| {} |\n",
+                cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                    .intersperse(" | ".into())
+                    .collect::<String>()
+            )
+        };
+        [
+            line(self.headers()),
+            line(widths.iter().map(|width| "-".repeat(*width)).collect()),
+        ]
+        .into_iter()
+        .chain(rows.iter().map(|row| {
+            line(
+                row.cells(self.extras)
+                    .iter()
+                    .map(|cell| escape(cell))
+                    .collect(),
+            )
+        }))
+        .chain([format!("\n{}\n", Self::summary(&rows))])
+        .collect()
+    }
+
+    fn csv(&self) -> String {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record(self.headers())
+            .and_then(|_| {
+                self.rows(false)
+                    .iter()
+                    .try_for_each(|row| writer.write_record(row.cells(self.extras)))
+            })
+            .expect("Writing to memory can't fail");
+        String::from_utf8(writer.into_inner().expect("Writing to memory can't fail"))
+            .expect("Cells are valid UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::filter::Filter;
+    use crate::listing::{ListFormat, Listing, SortBy};
+    use crate::panel::{Input, Panel};
+
+    fn panel() -> Panel {
+        serde_yaml::from_str::<Input>(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: Television
+                group: Living
+              - !Free
+              - !Occupied
+                text: Shed | Garden
+                group: Garden
+              - !Occupied
+                text: Doorbell
+                group: Living
+        "})
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_eq!(
+            indoc! {r"
+                | Port | Label          | Group  | Status   |
+                | ---- | -------------- | ------ | -------- |
+                | 02   | Shed \| Garden | Garden | occupied |
+                | 00   | Television     | Living | occupied |
+                | 03   | Doorbell       | Living | occupied |
+                | 01   |                |        | free     |
+
+                3 occupied, 1 free
+            "},
+            Listing::new(&mut panel(), SortBy::Group).render(ListFormat::Markdown)
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut panel = panel();
+        panel.set_filter(Filter {
+            groups: vec!["Living".into()],
+            ..Default::default()
+        });
+        assert_eq!(
+            indoc! {"
+                Port,Label,Group,Status
+                00,Television,Living,occupied
+                03,Doorbell,Living,occupied
+            "},
+            Listing::new(&mut panel, SortBy::Port).render(ListFormat::Csv)
+        );
+    }
+}
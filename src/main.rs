@@ -1,17 +1,508 @@
 use anyhow::anyhow;
-use patchvision::panel::{Input, Panel};
-use std::env::args;
-use std::fs::read_to_string;
+use clap::{Args, Parser, Subcommand};
+use patchvision::crossconnect::CrossConnect;
+use patchvision::diff::Diff;
+use patchvision::edit::Edit;
+use patchvision::filter::{condition, Filter, Highlight};
+use patchvision::format::Format;
+use patchvision::import::{from_csv, Columns, PORTS};
+use patchvision::inventory::Inventory;
+use patchvision::lint::{report, Lint, LintFormat, Rule, Severity};
+use patchvision::listing::{ListFormat, Listing, SortBy};
+use patchvision::panel::{LabelStyle, Panel, MAX_ROWS};
+use patchvision::plan::Plan;
+use patchvision::rack::Rack;
+use patchvision::slot::Slot;
+use patchvision::switch::Switch;
+use patchvision::theme::GenericTheme;
+use patchvision::trace::Trace;
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+
+/// Draw patch panels with a labelled balloon above every port, and keep their inventory
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Inventory file to render
+    file: Option<PathBuf>,
+
+    /// Format of the inventory (yaml, json or toml), instead of guessing it from the extension
+    #[arg(long, global = true)]
+    input_format: Option<Format>,
+
+    #[command(flatten)]
+    view: View,
+
+    #[command(flatten)]
+    selection: Selection,
+}
+
+/// Options that change how panels are drawn
+#[derive(Args)]
+struct View {
+    /// Show a legend of the groups below the panel
+    #[arg(long, global = true)]
+    legend: bool,
+
+    /// Colour the ports by this field instead of by group: state, label or any tag like vlan
+    #[arg(long, global = true)]
+    color_by: Option<String>,
+
+    /// Draw attention to a group or to ports like 3,5-7, and dim all others. Prefix group: or
+    /// ports: to tell which one it is
+    #[arg(long, global = true)]
+    highlight: Option<Highlight>,
+
+    /// Colour whole balloons, not just their text
+    #[arg(long, global = true)]
+    colored_outlines: bool,
+
+    /// Draw the labels as balloons, compact on a single line each, or vertical above the jacks
+    #[arg(long, global = true)]
+    labels: Option<LabelStyle>,
+
+    /// The lines of a vertical label, longer ones wrap to a second column
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    label_height: Option<u16>,
+
+    /// The most rows of labels above the panel, from 1 to 6
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..=MAX_ROWS as i64))]
+    rows: Option<u16>,
+
+    /// Turn the labels that don't fit in the rows into numbered footnotes below the panel
+    #[arg(long, global = true)]
+    footnotes: bool,
+
+    /// Shorten the labels that don't fit in the rows, with the abbreviations of the inventory
+    /// first, then by dropping vowels and cutting them off
+    #[arg(long, global = true)]
+    abbreviate: bool,
+
+    /// Explain where the balloons went: log every placement attempt, and show a ruler of columns
+    /// and the row of every balloon
+    #[arg(long, global = true)]
+    debug_layout: bool,
+
+    /// With --debug-layout, also dump the placeholders behind the balloons
+    #[arg(long, global = true, requires = "debug_layout")]
+    debug_placeholders: bool,
+}
+
+impl View {
+    /// The flags of the options that are given.
+    fn given(&self) -> Vec<&'static str> {
+        [
+            (self.legend, "--legend"),
+            (self.color_by.is_some(), "--color-by"),
+            (self.highlight.is_some(), "--highlight"),
+            (self.colored_outlines, "--colored-outlines"),
+            (self.labels.is_some(), "--labels"),
+            (self.label_height.is_some(), "--label-height"),
+            (self.rows.is_some(), "--rows"),
+            (self.footnotes, "--footnotes"),
+            (self.abbreviate, "--abbreviate"),
+            (self.debug_layout, "--debug-layout"),
+        ]
+        .into_iter()
+        .filter_map(|(given, flag)| given.then_some(flag))
+        .collect()
+    }
+
+    /// Fails for the options that only a drawing of a single panel can honour.
+    fn single_panel(&self, drawing: &str) -> anyhow::Result<()> {
+        if self.debug_layout {
+            return Err(anyhow!(
+                "--debug-layout explains the layout of a single panel, not of {}",
+                drawing
+            ));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, mut panel: Panel) -> anyhow::Result<Panel> {
+        if self.legend {
+            panel.show_legend(true);
+        }
+        if let Some(field) = &self.color_by {
+            panel.set_color_by(field).map_err(|error| anyhow!(error))?;
+        }
+        if let Some(highlight) = &self.highlight {
+            panel
+                .set_highlight(highlight.clone())
+                .map_err(|error| anyhow!(error))?;
+        }
+        if self.colored_outlines {
+            panel.color_outlines(true);
+        }
+        if let Some(labels) = self.labels {
+            panel.set_labels(labels);
+        }
+        if let Some(height) = self.label_height {
+            panel.set_label_height(height.into());
+        }
+        if let Some(rows) = self.rows {
+            panel.set_rows(rows.into());
+        }
+        if self.footnotes {
+            panel.use_footnotes(true);
+        }
+        if self.abbreviate {
+            panel.abbreviate(true);
+        }
+        panel.validate().map_err(|error| anyhow!(error))?;
+        Ok(panel)
+    }
+}
+
+/// Options that pick the ports of interest
+#[derive(Args)]
+struct Selection {
+    /// Only show the ports of this group, can be repeated
+    #[arg(long)]
+    group: Vec<String>,
+
+    /// Only show the ports where a tag (or label, group or state) has this value, like vlan=20
+    #[arg(long = "where", value_parser = condition)]
+    conditions: Vec<(String, String)>,
+
+    /// Leave out the other ports instead of dimming them
+    #[arg(long)]
+    hide: bool,
+}
+
+impl Selection {
+    fn apply(self, mut panel: Panel) -> Panel {
+        panel.set_filter(Filter {
+            groups: self.group,
+            conditions: self.conditions,
+            hide: self.hide,
+        });
+        panel
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render the panel described by an inventory file
+    Render {
+        file: PathBuf,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// List the ports of a panel as a table
+    List {
+        file: PathBuf,
+
+        /// Table format (text, markdown or csv)
+        #[arg(long, default_value_t = ListFormat::Text)]
+        format: ListFormat,
+
+        /// Order of the rows (port or group)
+        #[arg(long, default_value_t = SortBy::Port)]
+        sort: SortBy,
+
+        #[command(flatten)]
+        selection: Selection,
+    },
+
+    /// Check an inventory for common mistakes, failing if any rule at error level is broken
+    Lint {
+        file: PathBuf,
+
+        /// Output format (text or json)
+        #[arg(long, default_value_t = LintFormat::Text)]
+        format: LintFormat,
+
+        /// Fail on this rule, can be repeated
+        #[arg(long)]
+        deny: Vec<Rule>,
+
+        /// Only warn about this rule, can be repeated
+        #[arg(long)]
+        warn: Vec<Rule>,
+
+        /// Don't check this rule, can be repeated
+        #[arg(long)]
+        allow: Vec<Rule>,
+
+        /// The longest label that is fine
+        #[arg(long, default_value_t = Lint::MAX_LABEL)]
+        max_label: usize,
+    },
+
+    /// Assign a label and group to a port
+    Set {
+        file: PathBuf,
+        port: usize,
+        #[arg(long)]
+        text: String,
+        #[arg(long)]
+        group: String,
+    },
+
+    /// Mark a port as free
+    Free { file: PathBuf, port: usize },
+
+    /// Move the assignment of a port to a free port
+    Move {
+        file: PathBuf,
+        from: usize,
+        to: usize,
+    },
+
+    /// Exchange the assignments of two ports
+    Swap { file: PathBuf, a: usize, b: usize },
+
+    /// Propose the moves that give every group a block of neighbouring ports
+    Plan {
+        file: PathBuf,
+
+        /// The order of the blocks, like Living,Office. Other groups go after these.
+        #[arg(long, value_delimiter = ',')]
+        order: Vec<String>,
+    },
+
+    /// Compare two versions of an inventory, and render the new panel with the changes marked
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+
+        /// Print a plain list of the changes instead, like for CI
+        #[arg(long)]
+        summary: bool,
+    },
+
+    /// Render the front of a switch, with balloons above and below its ports
+    Switch { file: PathBuf },
+
+    /// Render a patch panel above a switch, with the patch cords between them
+    CrossConnect { file: PathBuf },
+
+    /// Follow a port from its wall outlet to the switch, by port number or by the label of the
+    /// port, its outlet or the host at the switch
+    Trace { file: PathBuf, query: String },
+
+    /// Render all devices of a rack file, stacked by rack unit
+    Rack { file: PathBuf },
+
+    /// Build an inventory from a CSV export of port assignments
+    Import {
+        file: PathBuf,
+
+        /// Write the inventory to this file, in the format matching its extension
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Render the imported panel instead of printing the inventory
+        #[arg(long, conflicts_with = "output")]
+        render: bool,
+
+        /// Column holding the port number
+        #[arg(long, default_value_t = Columns::default().port)]
+        port_column: String,
+
+        /// Column holding the label
+        #[arg(long, default_value_t = Columns::default().label)]
+        label_column: String,
+
+        /// Column holding the group
+        #[arg(long, default_value_t = Columns::default().group)]
+        group_column: String,
+
+        /// Number of ports of the panel
+        #[arg(long, default_value_t = PORTS)]
+        ports: usize,
+
+        /// Theme of the imported panel (ascii or rounded)
+        #[arg(long, default_value = "ascii")]
+        theme: GenericTheme,
+    },
+}
 
 fn main() -> anyhow::Result<()> {
-    let yaml = read_to_string(
-        args()
-            .nth(1)
-            .ok_or(anyhow!("Please pass input yaml file"))?,
-    )?;
-    let input: Input = serde_yaml::from_str(&yaml)?;
-    let mut panel: Panel = input.into();
-    println!("{}", panel.render());
+    let cli = Cli::parse();
+    let format = cli.input_format;
+    let view = cli.view;
+    match cli.command {
+        None => render(
+            cli.file.ok_or(anyhow!("Please pass an inventory file"))?,
+            format,
+            &view,
+            cli.selection,
+        ),
+        Some(Command::Render { file, selection }) => render(file, format, &view, selection),
+        Some(Command::List {
+            file,
+            format: list_format,
+            sort,
+            selection,
+        }) => {
+            let mut panel = selection.apply(view.apply(Inventory::open(file, format)?.panel())?);
+            print!("{}", Listing::new(&mut panel, sort).render(list_format));
+            Ok(())
+        }
+        Some(Command::Lint {
+            file,
+            format: lint_format,
+            deny,
+            warn,
+            allow,
+            max_label,
+        }) => {
+            let mut lint = Lint::default();
+            lint.set_max_label(max_label);
+            for (rules, severity) in [
+                (deny, Severity::Error),
+                (warn, Severity::Warning),
+                (allow, Severity::Allow),
+            ] {
+                for rule in rules {
+                    lint.set_severity(rule, severity);
+                }
+            }
+            let problems = lint.check(&mut Inventory::open(file, format)?.panel());
+            println!("{}", report(&problems, lint_format)?);
+            if problems
+                .iter()
+                .any(|problem| problem.severity() == Severity::Error)
+            {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(Command::Set {
+            file,
+            port,
+            text,
+            group,
+        }) => edit(
+            file,
+            format,
+            &view,
+            Edit::Set {
+                port,
+                slot: Slot::occupied(text, group),
+            },
+        ),
+        Some(Command::Free { file, port }) => edit(file, format, &view, Edit::Free { port }),
+        Some(Command::Move { file, from, to }) => {
+            edit(file, format, &view, Edit::Move { from, to })
+        }
+        Some(Command::Swap { file, a, b }) => edit(file, format, &view, Edit::Swap { a, b }),
+        Some(Command::Plan { file, order }) => {
+            let mut before = view.apply(Inventory::open(file, format)?.panel())?;
+            let plan = Plan::new(&before, &order).map_err(|error| anyhow!(error))?;
+            let steps = plan.describe(&before);
+            let rendered = before.render();
+            let mut after = plan.apply(before);
+            println!(
+                "Before:{}\n\nSteps:\n{}\n\nAfter:{}",
+                rendered,
+                steps,
+                after.render()
+            );
+            Ok(())
+        }
+        Some(Command::Diff { old, new, summary }) => {
+            let old = Inventory::open(old, format)?.panel();
+            let mut new = view.apply(Inventory::open(new, format)?.panel())?;
+            let diff = Diff::between(&old, &new);
+            if summary {
+                print!("{}", diff.summary());
+            } else {
+                diff.mark(&mut new);
+                println!("{}", new.render());
+            }
+            Ok(())
+        }
+        Some(Command::Switch { file }) => {
+            let given = view.given();
+            if !given.is_empty() {
+                return Err(anyhow!(
+                    "The view options are for panels, a switch can't use {}",
+                    given.join(", ")
+                ));
+            }
+            println!("{}", Switch::open(file, format)?.render());
+            Ok(())
+        }
+        Some(Command::CrossConnect { file }) => {
+            view.single_panel("a cross-connect")?;
+            let mut cross_connect =
+                CrossConnect::open(file, format)?.map_panel(|panel| view.apply(panel))?;
+            println!("{}", cross_connect.render()?);
+            Ok(())
+        }
+        Some(Command::Trace { file, query }) => {
+            view.single_panel("a trace")?;
+            let trace = Trace::open(file, format, &query)?.map_panel(|panel| view.apply(panel))?;
+            println!("{}", trace.render()?);
+            Ok(())
+        }
+        Some(Command::Rack { file }) => {
+            view.single_panel("a rack")?;
+            let mut rack = Rack::open(file, format)?.map_panels(|panel| view.apply(panel))?;
+            println!("{}", rack.render());
+            Ok(())
+        }
+        Some(Command::Import {
+            file,
+            output,
+            render,
+            port_column,
+            label_column,
+            group_column,
+            ports,
+            theme,
+        }) => {
+            let columns = Columns {
+                port: port_column,
+                label: label_column,
+                group: group_column,
+            };
+            let input = from_csv(&read_to_string(file)?, &columns, ports, theme)?;
+            if render {
+                println!("{}", view.apply(Panel::from(input))?.render());
+            } else if let Some(output) = output {
+                write(&output, Format::detect(&output).serialize(&input)?)?;
+            } else {
+                print!("{}", Format::Yaml.serialize(&input)?);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn render(
+    file: PathBuf,
+    format: Option<Format>,
+    view: &View,
+    selection: Selection,
+) -> anyhow::Result<()> {
+    let mut panel = selection.apply(view.apply(Inventory::open(file, format)?.panel())?);
+    match view.debug_layout {
+        true => println!("{}", panel.render_debug(view.debug_placeholders)),
+        false => println!("{}", panel.render()),
+    }
+
+    Ok(())
+}
+
+fn edit(file: PathBuf, format: Option<Format>, view: &View, edit: Edit) -> anyhow::Result<()> {
+    let mut inventory = Inventory::open(file, format)?;
+    // The view options are checked against the panel before the edit, and still apply after it
+    let mut panel = view.apply(inventory.panel())?;
+    let before = panel.render_ports(&edit.ports());
+    inventory.apply(&edit)?;
+    panel.edit(&edit).map_err(|error| anyhow!(error))?;
+    inventory.save()?;
+    let after = panel.render_ports(&edit.ports());
+    println!("Before:{}\n\nAfter:{}", before, after);
 
     Ok(())
 }
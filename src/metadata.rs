@@ -0,0 +1,71 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// What a printed diagram says about the panel it shows.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    /// The rack or room the panel is in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) location: Option<String>,
+    /// The rack unit the panel is mounted at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) unit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) updated: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) maintainer: Option<String>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        self == &Metadata::default()
+    }
+
+    /// Which panel this is, like `Panel A · Rack 2 · U12`.
+    pub(crate) fn header(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            self.name.clone(),
+            self.location.clone(),
+            self.unit.map(|unit| format!("U{}", unit)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+
+    /// Who keeps the diagram up to date, like `Updated 2024-03-01 by Jan`.
+    pub(crate) fn footer(&self) -> Option<String> {
+        match (self.updated, &self.maintainer) {
+            (Some(updated), Some(maintainer)) => {
+                Some(format!("Updated {} by {}", updated, maintainer))
+            }
+            (Some(updated), None) => Some(format!("Updated {}", updated)),
+            (None, Some(maintainer)) => Some(format!("Maintained by {}", maintainer)),
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::metadata::Metadata;
+
+    #[test]
+    fn test_header_footer() {
+        let metadata: Metadata = serde_yaml::from_str(indoc! {"
+            name: Panel A
+            unit: 12
+            updated: 2024-03-01
+        "})
+        .unwrap();
+        assert_eq!(Some("Panel A · U12".into()), metadata.header());
+        assert_eq!(Some("Updated 2024-03-01".into()), metadata.footer());
+        assert_eq!(None, Metadata::default().header());
+        assert_eq!(None, Metadata::default().footer());
+    }
+}
@@ -1,100 +1,1237 @@
-use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-use crate::balloon::{max_shift, Balloon};
-use crate::slot::Slot;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::abbreviation::{next, shorten, Stage};
+use crate::balloon::{arrow, inner_width, upright, Balloon, Shape};
+use crate::edit::Edit;
+use crate::filter::{Filter, Highlight};
+use crate::legend::{entries, Group};
+use crate::metadata::Metadata;
+use crate::placeholder::{Color, Emphasis, Style, Tone};
+use crate::slot::{Pin, Side, Slot};
 use crate::template::Template;
 use crate::theme::{GenericTheme, Theme};
+use crate::trace::Wiring;
+
+pub const MAX_ROWS: usize = 6;
+
+/// The lines a vertical label gets, unless the inventory says otherwise
+const LABEL_HEIGHT: usize = 8;
+
+/// How the labels are drawn above the jacks.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelStyle {
+    /// In a box, three lines high
+    #[default]
+    Balloons,
+    /// A single line of text with a short leader to the arrow, like `Playstation ─┬┐`
+    Compact,
+    /// Written top to bottom straight above the jack, for short labels
+    Vertical,
+}
+
+impl LabelStyle {
+    pub fn is_default(&self) -> bool {
+        *self == LabelStyle::default()
+    }
+
+    /// The shape of the labels, if they are placed in rows at all.
+    pub(crate) fn shape(&self) -> Option<Shape> {
+        match self {
+            LabelStyle::Balloons => Some(Shape::Boxed),
+            LabelStyle::Compact => Some(Shape::Compact),
+            LabelStyle::Vertical => None,
+        }
+    }
+}
+
+impl FromStr for LabelStyle {
+    type Err = String;
 
-const MAX_ROWS: usize = 6;
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "balloons" => Ok(LabelStyle::Balloons),
+            "compact" => Ok(LabelStyle::Compact),
+            "vertical" => Ok(LabelStyle::Vertical),
+            _ => Err(format!(
+                "Unknown label style '{}', expected balloons, compact or vertical",
+                string
+            )),
+        }
+    }
+}
+
+impl Display for LabelStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelStyle::Balloons => write!(f, "balloons"),
+            LabelStyle::Compact => write!(f, "compact"),
+            LabelStyle::Vertical => write!(f, "vertical"),
+        }
+    }
+}
 
-#[derive(Clone, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Input {
-    pub(crate) slots: Vec<Slot>,
     pub(crate) theme: GenericTheme,
+    /// Says which panel this is, on the diagram
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub(crate) metadata: Metadata,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) legend: bool,
+    /// Colour whole balloons, see [`Panel::color_outlines`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) colored_outlines: bool,
+    #[serde(default, skip_serializing_if = "LabelStyle::is_default")]
+    pub(crate) labels: LabelStyle,
+    /// The lines of a vertical label, longer ones wrap to a second column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) label_height: Option<usize>,
+    /// The most rows of labels above the panel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rows: Option<usize>,
+    /// Turn the labels that don't fit in the rows into numbered footnotes below the panel
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) footnotes: bool,
+    /// Shorten the labels that don't fit in the rows, with the abbreviations first
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) abbreviate: bool,
+    /// Short forms of words and phrases in labels, like `Television: TV`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) abbreviations: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) groups: BTreeMap<String, Group>,
+    /// Where the jacks lead, for tracing a port from the wall to the switch
+    #[serde(default, skip_serializing_if = "Wiring::is_empty")]
+    pub(crate) wiring: Wiring,
+    pub(crate) slots: Vec<Slot>,
 }
 
 pub struct Panel {
     pub(crate) slots: Vec<Slot>,
     pub(crate) theme: Box<dyn Theme>,
+    pub(crate) legend: bool,
+    pub(crate) labels: LabelStyle,
+    /// The lines of a vertical label
+    pub(crate) label_height: usize,
+    /// The most rows of labels above the panel
+    pub(crate) rows: usize,
+    pub(crate) footnotes: bool,
+    /// Shortens the labels that don't fit
+    pub(crate) abbreviate: bool,
+    /// Short forms of words and phrases in labels
+    pub(crate) abbreviations: BTreeMap<String, String>,
+    pub(crate) metadata: Metadata,
+    pub(crate) groups: BTreeMap<String, Group>,
+    pub(crate) filter: Filter,
+    /// The field of a slot that picks its colour, see [`Slot::field`]
+    pub(crate) color_by: String,
+    pub(crate) highlight: Option<Highlight>,
+    /// Balloon texts that replace the labels of some ports, like to mark what changed
+    pub(crate) captions: BTreeMap<usize, String>,
 }
 
 impl From<Input> for Panel {
     fn from(input: Input) -> Self {
+        let mut theme = input.theme.build();
+        theme.color_outlines(input.colored_outlines);
         Panel {
             slots: input.slots,
-            theme: input.theme.new(),
+            theme,
+            legend: input.legend,
+            labels: input.labels,
+            label_height: input.label_height.unwrap_or(LABEL_HEIGHT),
+            rows: input.rows.unwrap_or(MAX_ROWS),
+            footnotes: input.footnotes,
+            abbreviate: input.abbreviate,
+            abbreviations: input.abbreviations,
+            metadata: input.metadata,
+            groups: input.groups,
+            filter: Filter::default(),
+            color_by: "group".into(),
+            highlight: None,
+            captions: BTreeMap::new(),
         }
     }
 }
 
+impl Input {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.slots.len() > Balloon::RANGE + 1 {
+            return Err(format!(
+                "A panel holds at most {} slots, found {}",
+                Balloon::RANGE + 1,
+                self.slots.len()
+            ));
+        }
+        for (port, slot) in self.slots.iter().enumerate() {
+            if let Some(label) = slot.label() {
+                if label.trim().is_empty() {
+                    return Err(format!("Port {:02} has an empty label", port));
+                }
+            }
+            if let Some(group) = slot.group() {
+                if group.trim().is_empty() {
+                    return Err(format!("Port {:02} has an empty group", port));
+                }
+            }
+        }
+        if let Some(rows) = self.rows.filter(|rows| !(1..=MAX_ROWS).contains(rows)) {
+            return Err(format!("Labels take 1 to {} rows, not {}", MAX_ROWS, rows));
+        }
+        if self.label_height == Some(0) {
+            return Err("Vertical labels need a height of at least one line".into());
+        }
+        let rows = self.rows.unwrap_or(MAX_ROWS);
+        check_pins(&self.slots, self.labels, rows)?;
+        self.wiring.validate(self.slots.len())
+    }
+}
+
 impl Panel {
     pub fn render(&mut self) -> String {
-        format!(
-            "{}\n{}",
-            self.layout().render(&self.theme),
-            self.theme.render_panel(self)
+        self.render_ports(&(0..self.slots.len()).collect::<Vec<_>>())
+    }
+
+    /// Renders the whole panel, but only draws the balloons of the given ports.
+    pub fn render_ports(&mut self, ports: &[usize]) -> String {
+        let layout = self.layout(ports, &mut Vec::new());
+        let balloons = layout.balloons.render(&*self.theme);
+        self.compose(balloons, layout.footnotes)
+    }
+
+    /// Puts the rendered balloons together with the header, the frame, the footnotes and the
+    /// legend.
+    fn compose(&mut self, balloons: String, footnotes: Vec<String>) -> String {
+        let (frame, header) = self.theme.render_panel(self);
+        let mut rendered = format!("{}\n{}", balloons, frame);
+        if !footnotes.is_empty() {
+            rendered = format!("{}\n{}", rendered, footnotes.join("\n"));
+        }
+        if let Some(header) = header {
+            rendered = format!("\n{}{}", header, rendered);
+        }
+        if self.legend {
+            let entries = entries(self);
+            rendered = format!("{}\n{}", rendered, self.theme.render_legend(&entries));
+        }
+        rendered
+    }
+
+    /// Renders the balloons and the panel itself separately, for drawings that stack panels.
+    pub(crate) fn render_layers(&mut self) -> (String, String) {
+        let all: Vec<usize> = (0..self.slots.len()).collect();
+        let Layout {
+            balloons,
+            footnotes,
+            ..
+        } = self.layout(&all, &mut Vec::new());
+        let (panel, header) = self.theme.render_panel(self);
+        let balloons = balloons.render(&*self.theme);
+        (
+            match header {
+                // Above the balloons, as the frame is glued to whatever comes below it
+                Some(header) => format!("\n{}{}", header, balloons),
+                None => balloons,
+            },
+            footnotes
+                .into_iter()
+                .fold(panel, |panel, footnote| format!("{}\n{}", panel, footnote)),
         )
     }
-    fn shift(
+
+    /// Applies an edit to the slots, and keeps the options of how the panel is drawn.
+    pub fn edit(&mut self, edit: &Edit) -> Result<(), String> {
+        edit.apply(&mut self.slots)
+    }
+
+    /// Shows or hides the legend of the groups below the panel.
+    pub fn show_legend(&mut self, legend: bool) {
+        self.legend = legend;
+    }
+
+    /// Draws the labels in another style, like compact to fit more rows in fewer lines.
+    pub fn set_labels(&mut self, labels: LabelStyle) {
+        self.labels = labels;
+    }
+
+    /// Gives vertical labels another number of lines, at least one.
+    pub fn set_label_height(&mut self, height: usize) {
+        self.label_height = height.max(1);
+    }
+
+    /// Limits the rows of labels above the panel, to between one and [`MAX_ROWS`].
+    pub fn set_rows(&mut self, rows: usize) {
+        self.rows = rows.clamp(1, MAX_ROWS);
+    }
+
+    /// Turns the labels that don't fit in the rows into footnotes, instead of leaving them out.
+    pub fn use_footnotes(&mut self, footnotes: bool) {
+        self.footnotes = footnotes;
+    }
+
+    /// Shortens the labels that don't fit in the rows, with the abbreviations of the inventory
+    /// first. Listings keep the full labels.
+    pub fn abbreviate(&mut self, abbreviate: bool) {
+        self.abbreviate = abbreviate;
+    }
+
+    /// Checks the pins of the slots against the labels and rows as they are drawn now, after the
+    /// view options changed them.
+    pub fn validate(&self) -> Result<(), String> {
+        check_pins(&self.slots, self.labels, self.rows)
+    }
+
+    /// Colours whole balloons, see [`Theme::color_outlines`].
+    pub fn color_outlines(&mut self, colored: bool) {
+        self.theme.color_outlines(colored);
+    }
+
+    /// Dims or hides the ports that don't match the filter, in drawings and listings.
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    /// Draws attention to some ports, and dims all others. The ports have to be on the panel,
+    /// and a group has to have some.
+    pub fn set_highlight(&mut self, highlight: Highlight) -> Result<(), String> {
+        match &highlight {
+            Highlight::Group(group)
+                if !self.slots.iter().any(|slot| slot.group() == Some(group)) =>
+            {
+                return Err(format!("No port is in group {}", group));
+            }
+            Highlight::Ports(ports) => {
+                if let Some(port) = ports.iter().find(|port| **port >= self.slots.len()) {
+                    return Err(format!("Port {:02} doesn't exist", port));
+                }
+            }
+            Highlight::Group(_) => {}
+        }
+        self.highlight = Some(highlight);
+        Ok(())
+    }
+
+    /// Shows a text in the balloon of a port instead of its label, even if the port has none.
+    pub(crate) fn set_caption(&mut self, port: usize, text: impl Into<String>) {
+        self.captions.insert(port, text.into());
+    }
+
+    /// How much attention the balloon and slot of a port draw.
+    pub(crate) fn emphasis(&self, port: usize, slot: &Slot) -> Emphasis {
+        if self.filter.mutes(slot) {
+            return Emphasis::Muted;
+        }
+        match &self.highlight {
+            Some(highlight) if highlight.matches(port, slot) => Emphasis::Highlighted,
+            Some(_) => Emphasis::Muted,
+            None => Emphasis::Normal,
+        }
+    }
+
+    /// Colours the ports by their group, state or a tag instead of by their group. A tag has to be
+    /// on at least one port, so a typo doesn't leave every port neutral.
+    pub fn set_color_by(&mut self, field: impl Into<String>) -> Result<(), String> {
+        let field = field.into();
+        let tags: Vec<&String> = self
+            .slots
+            .iter()
+            .filter_map(Slot::tags)
+            .flat_map(BTreeMap::keys)
+            .sorted()
+            .dedup()
+            .collect();
+        if !["group", "label", "state"].contains(&field.as_str()) && !tags.contains(&&field) {
+            return Err(match tags.is_empty() {
+                true => format!(
+                    "No port has a field '{}', expected group, label or state",
+                    field
+                ),
+                false => format!(
+                    "No port has a field '{}', expected group, label, state or a tag: {}",
+                    field,
+                    tags.iter().join(", ")
+                ),
+            });
+        }
+        self.color_by = field;
+        Ok(())
+    }
+
+    /// The value of the slot that picks its colour, if it has one.
+    pub(crate) fn color_key(&self, slot: &Slot) -> Option<String> {
+        slot.field(&self.color_by)
+    }
+
+    /// The colour of a slot, neutral if it lacks the colouring field.
+    pub(crate) fn color(&mut self, slot: &Slot) -> Option<Color> {
+        self.color_key(slot).map(|key| self.theme.style_group(&key))
+    }
+
+    /// Claims the colours of all keys in port order, so they don't depend on what is shown.
+    pub(crate) fn style_keys(&mut self) {
+        for slot in self.slots.clone() {
+            self.color(&slot);
+        }
+    }
+
+    /// The looks of the balloon of a slot.
+    fn style_slot(&mut self, port: usize, slot: &Slot) -> Style {
+        let color = self.color(slot).unwrap_or(Color::NEUTRAL);
+        let tone = if slot.tentative() {
+            Tone::Tentative
+        } else {
+            Tone::Firm
+        };
+        Style {
+            color,
+            tone,
+            emphasis: self.emphasis(port, slot),
+        }
+    }
+
+    /// The balloons of the given ports, in port order.
+    fn balloons(&mut self, ports: &[usize]) -> Vec<(usize, String, Style, Pin)> {
+        let mut balloons = Vec::new();
+        self.style_keys();
+        for (index, slot) in self.slots.clone().iter().cloned().enumerate() {
+            if !ports.contains(&index) || self.filter.hides(&slot) {
+                continue;
+            }
+            let caption = self.captions.get(&index).cloned();
+            if let Some(text) = caption.or(slot.label().map(String::from)) {
+                let style = self.style_slot(index, &slot);
+                balloons.push((index, text, style, slot.pin()));
+            }
+        }
+        balloons
+    }
+
+    /// Places the balloons of the given ports in rows. Labels that don't fit are shortened or
+    /// become footnotes if asked for, and the footnotes come along as the lines of their list.
+    fn stack_rows(
         &mut self,
-        index: usize,
-        text: &str,
-        group: &str,
-        row: usize,
-        stack: &mut Vec<Balloon>,
-    ) -> bool {
-        let previous = stack.last().unwrap();
-        for shift in (0..=max_shift(text, index)).rev() {
-            let attempt = Balloon::new(
-                self.theme.style_group(group),
-                text.to_string(),
-                index,
-                row,
-                shift,
-            );
-            if let Ok(balloon) = attempt {
-                if !previous.overlaps(&balloon) && balloon.end() <= Balloon::WIDTH {
-                    stack.push(balloon);
-                    return true;
+        ports: &[usize],
+        shape: Shape,
+        log: &mut Vec<String>,
+    ) -> (Vec<Vec<Balloon>>, Vec<String>) {
+        let balloons = self.balloons(ports);
+        match (self.footnotes, self.abbreviate) {
+            (false, false) => (stack(balloons, shape, self.rows, log), Vec::new()),
+            (footnotes, abbreviate) => fit(
+                balloons,
+                shape,
+                self.rows,
+                abbreviate.then_some(&self.abbreviations),
+                footnotes,
+                log,
+            ),
+        }
+    }
+
+    /// Draws the labels of the given ports, and lists the footnotes if there are any.
+    fn layout(&mut self, ports: &[usize], log: &mut Vec<String>) -> Layout {
+        match self.labels.shape() {
+            Some(shape) => {
+                let (grid, footnotes) = self.stack_rows(ports, shape, log);
+                Layout {
+                    balloons: overlay(&grid),
+                    footnotes,
+                    rows: (0..grid.len())
+                        .filter(|row| !grid[*row].is_empty())
+                        .map(|row| (row, shape.lines()))
+                        .collect(),
+                }
+            }
+            None => {
+                log.push("Vertical labels go straight above their jacks, without rows".into());
+                Layout {
+                    balloons: columns(self.balloons(ports), self.label_height),
+                    footnotes: Vec::new(),
+                    rows: Vec::new(),
                 }
             }
         }
-        false
-    }
-
-    fn place(&mut self, grid: &mut Vec<Vec<Balloon>>, index: usize, text: String, group: String) {
-        for row in 0..MAX_ROWS {
-            match grid.get_mut(row) {
-                None => {
-                    grid.push(vec![Balloon::left(
-                        self.theme.style_group(&group),
-                        text.to_string(),
-                        index,
-                        row,
-                    )
-                    .unwrap()]);
-                    return;
+    }
+
+    /// Renders the panel like [`Panel::render`], with a log of every attempt to place a balloon
+    /// above it, and a ruler of columns and the row of every balloon next to the balloons. The
+    /// placeholders behind the balloons can be dumped as well, one code per column.
+    pub fn render_debug(&mut self, placeholders: bool) -> String {
+        let all: Vec<usize> = (0..self.slots.len()).collect();
+        let mut log = Vec::new();
+        let Layout {
+            balloons: template,
+            footnotes,
+            rows,
+        } = self.layout(&all, &mut log);
+        let (height, width) = (template.grid.rows(), template.grid.cols());
+        let dump = placeholders.then(|| template.dump());
+
+        let ruler = [
+            (0..width)
+                .map(|column| match column % 10 {
+                    0 => char::from_digit((column / 10 % 10) as u32, 10).unwrap_or(' '),
+                    _ => ' ',
+                })
+                .collect::<String>(),
+            (0..width)
+                .filter_map(|column| char::from_digit((column % 10) as u32, 10))
+                .collect(),
+        ];
+        // The text of the balloons of a row is the middle line of the row, from the bottom up
+        let labelled: Vec<(usize, usize)> = rows
+            .into_iter()
+            .filter_map(|(row, lines): (usize, usize)| {
+                (height + lines / 2)
+                    .checked_sub(lines * row + lines)
+                    .map(|line| (line, row))
+            })
+            .collect();
+        let balloons: Vec<String> = template
+            .render(&*self.theme)
+            .lines()
+            .skip(1)
+            .enumerate()
+            .map(
+                |(line, text)| match labelled.iter().find(|(at, _)| *at == line) {
+                    Some((_, row)) => format!("{}  row {}", text, row),
+                    None => text.to_string(),
+                },
+            )
+            .collect();
+
+        let balloons = format!("\n{}\n{}", ruler.join("\n"), balloons.join("\n"));
+        let mut rendered = format!("{}\n{}", log.join("\n"), self.compose(balloons, footnotes));
+        if let Some(dump) = dump {
+            rendered = format!("{}\n\n{}", rendered, dump);
+        }
+        rendered
+    }
+}
+
+/// The balloons of a panel, laid out.
+struct Layout {
+    balloons: Template,
+    /// The labels that didn't fit
+    footnotes: Vec<String>,
+    /// The rows that have balloons, with the lines each of them takes
+    rows: Vec<(usize, usize)>,
+}
+
+/// Lays out balloons for the given positions. Pinned balloons go first, where they are pinned.
+/// The others go each as far left as it fits, in rows as close to the positions as possible.
+pub(crate) fn arrange(balloons: Vec<(usize, String, Style, Pin)>, shape: Shape) -> Template {
+    overlay(&stack(balloons, shape, MAX_ROWS, &mut Vec::new()))
+}
+
+/// Writes every label straight above its jack, see [`LabelStyle::Vertical`].
+fn columns(balloons: Vec<(usize, String, Style, Pin)>, height: usize) -> Template {
+    balloons
+        .iter()
+        .fold(Template::default(), |template, (index, text, style, _)| {
+            template.overlay(upright(*style, text, *index, height))
+        })
+}
+
+/// Places the balloons in rows, like [`stack`], and makes room for as long as some don't fit.
+///
+/// With a dictionary of abbreviations, a label that didn't fit is shortened a step further, see
+/// [`shorten`], the longest first. Once those can't get any shorter, so are the others. With
+/// footnotes, the longest label left becomes a footnote next: its balloon only shows a marker, and
+/// the label goes in the list below the panel. Returns the rows and the lines of the list.
+fn fit(
+    balloons: Vec<(usize, String, Style, Pin)>,
+    shape: Shape,
+    rows: usize,
+    abbreviations: Option<&BTreeMap<String, String>>,
+    footnotes: bool,
+    log: &mut Vec<String>,
+) -> (Vec<Vec<Balloon>>, Vec<String>) {
+    // One that doesn't fit even on its own, like one pinned below the last row, would only push
+    // all the others out
+    let (balloons, hopeless): (Vec<_>, Vec<_>) =
+        balloons.into_iter().partition(|(index, text, style, pin)| {
+            let balloon = (*index, text.as_str(), *style, *pin);
+            place(&mut Vec::new(), balloon, shape, rows, &[], &mut Vec::new())
+        });
+    for (index, text, ..) in hopeless {
+        log.push(format!(
+            "{:02} {}: doesn't fit even alone, not drawn",
+            index, text
+        ));
+    }
+    let dictionary = abbreviations.cloned().unwrap_or_default();
+    let mut stages: BTreeMap<usize, Stage> = BTreeMap::new();
+    let mut demoted: Vec<usize> = Vec::new();
+    loop {
+        // Number the footnotes in port order, as the markers take up room as well
+        let mut list = Vec::new();
+        let marked: Vec<_> = balloons
+            .iter()
+            .map(|(index, text, style, pin)| {
+                // A shorter text can't keep the shift of the label
+                let unshifted = Pin {
+                    shift: None,
+                    ..*pin
+                };
+                if demoted.contains(index) {
+                    let marker = marker(list.len() + 1);
+                    list.push(format!("{} {}", marker, text));
+                    return (*index, marker, *style, unshifted);
                 }
-                Some(stack) => {
-                    if self.shift(index, &text, &group, row, stack) {
-                        return;
-                    }
+                match stages.get(index) {
+                    Some(stage) => (
+                        *index,
+                        shorten(text, *stage, &dictionary),
+                        *style,
+                        unshifted,
+                    ),
+                    None => (*index, text.clone(), *style, *pin),
                 }
+            })
+            .collect();
+        let mut attempts = Vec::new();
+        let grid = stack(marked, shape, rows, &mut attempts);
+        let placed: Vec<usize> = grid.iter().flatten().map(Balloon::slot).collect();
+        if placed.len() == balloons.len() {
+            log.extend(attempts);
+            return (grid, list);
+        }
+        let fitted = format!("{} of {} labels fit", placed.len(), balloons.len());
+
+        let left = || {
+            balloons
+                .iter()
+                .filter(|(index, ..)| !demoted.contains(index))
+                .rev()
+        };
+        // The longest current text that can get shorter still, of the ones that didn't fit first
+        let stage = |index: &usize| stages.get(index).copied().unwrap_or_default();
+        let shorter = |unplaced: bool| {
+            left()
+                .filter(|(index, ..)| !unplaced || !placed.contains(index))
+                .filter_map(|(index, text, ..)| {
+                    next(text, stage(index), &dictionary).map(|next| (*index, text, next))
+                })
+                .max_by_key(|(index, text, _)| {
+                    inner_width(&shorten(text, stage(index), &dictionary))
+                })
+        };
+        if let Some((index, text, next)) = abbreviations
+            .is_some()
+            .then(|| shorter(true).or_else(|| shorter(false)))
+            .flatten()
+        {
+            log.push(format!(
+                "{}, {:02} {} is shortened to {}",
+                fitted,
+                index,
+                text,
+                shorten(text, next, &dictionary)
+            ));
+            stages.insert(index, next);
+            continue;
+        }
+        match left().max_by_key(|(_, text, ..)| inner_width(text)) {
+            Some((index, text, ..)) if footnotes => {
+                log.push(format!(
+                    "{}, {:02} {} becomes a footnote",
+                    fitted, index, text
+                ));
+                demoted.push(*index);
+            }
+            _ => {
+                log.extend(attempts);
+                return (grid, list);
             }
         }
     }
+}
 
-    fn layout(&mut self) -> Template {
-        let mut grid: Vec<Vec<Balloon>> = Default::default();
-        for (index, slot) in self.slots.clone().iter().cloned().enumerate() {
-            if let Slot::Occupied { text, group } = slot {
-                self.place(&mut grid, index, text, group);
+/// A footnote number in superscript, like `¹²`.
+fn marker(number: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    number
+        .to_string()
+        .chars()
+        .filter_map(|digit| digit.to_digit(10))
+        .map(|digit| DIGITS[digit as usize])
+        .collect()
+}
+
+/// Places the balloons in at most the given number of rows, logging every attempt.
+fn stack(
+    balloons: Vec<(usize, String, Style, Pin)>,
+    shape: Shape,
+    rows: usize,
+    log: &mut Vec<String>,
+) -> Vec<Vec<Balloon>> {
+    let mut grid: Vec<Vec<Balloon>> = Default::default();
+    let (pinned, unpinned): (Vec<_>, Vec<_>) = balloons
+        .into_iter()
+        .partition(|(_, _, _, pin)| !pin.is_empty());
+    let order: Vec<_> = pinned.into_iter().chain(unpinned).collect();
+    for (placed, (index, text, style, pin)) in order.iter().enumerate() {
+        let balloon = (*index, text.as_str(), *style, *pin);
+        place(&mut grid, balloon, shape, rows, &order[placed + 1..], log);
+    }
+    grid
+}
+
+/// Draws all balloons on top of each other.
+fn overlay(grid: &[Vec<Balloon>]) -> Template {
+    grid.iter()
+        .flatten()
+        .fold(Template::default(), |template, balloon| {
+            template.overlay(balloon.pre_render())
+        })
+}
+
+/// How much a balloon may get in the way of others, from most to least careful.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Clearance {
+    /// Arrows may cross balloons anywhere, and are drawn over the text they run into
+    Text,
+    /// Arrows may cross balloons, but only where they show without hiding any text, see
+    /// [`Balloon::crossable`]
+    Overlap,
+    /// Arrows may not cross balloons, so a balloon can't cover the arrows of the balloons above
+    /// it, and its own arrow can't go through the balloons below it
+    Arrows,
+    /// The balloon doesn't cover the arrows of balloons that are still to be placed either, if
+    /// those can't go in a row below it and would have to cross it
+    Pending,
+}
+
+/// What is in the way of a balloon, if anything. The balloons still to be placed only matter
+/// with [`Clearance::Pending`].
+fn obstacle(
+    grid: &[Vec<Balloon>],
+    balloon: &Balloon,
+    row: usize,
+    (pending, shape): (&[(usize, String, Style, Pin)], Shape),
+    clearance: Clearance,
+) -> Option<String> {
+    if balloon.end() > Balloon::WIDTH {
+        return Some(format!("past the edge at {}", Balloon::WIDTH));
+    }
+    let describe = |other: &Balloon| format!("{:02} {}", other.slot(), other.text());
+    if let Some(other) = grid[row].iter().find(|other| other.overlaps(balloon)) {
+        return Some(format!(
+            "overlaps {} at columns {}-{}",
+            describe(other),
+            other.start(),
+            other.end()
+        ));
+    }
+    // Whether an arrow may not go through a balloon
+    let blocks = |balloon: &Balloon, arrow: usize| {
+        balloon.covers(arrow)
+            && match clearance {
+                Clearance::Text => false,
+                Clearance::Overlap => !balloon.crossable(arrow),
+                Clearance::Arrows | Clearance::Pending => true,
             }
+    };
+    let mut above = grid.iter().skip(row + 1).flatten();
+    if let Some(other) = above.find(|other| blocks(balloon, other.arrow())) {
+        return Some(format!("covers the arrow of {}", describe(other)));
+    }
+    let mut below = grid[..row].iter().flatten();
+    if let Some(other) = below.find(|other| blocks(other, balloon.arrow())) {
+        return Some(format!("its arrow crosses {}", describe(other)));
+    }
+    if clearance >= Clearance::Pending {
+        let blocked = pending.iter().find(|(index, text, style, pin)| {
+            balloon.covers(arrow(*index))
+                && !fits_below(grid, (*index, text, *style, *pin), shape, row)
+        });
+        if let Some((index, text, ..)) = blocked {
+            return Some(format!(
+                "covers the arrow of {:02} {}, which can't go in a row below",
+                index, text
+            ));
         }
-        grid.iter()
+    }
+    None
+}
+
+/// The balloons a label may get in a row, within the limits of its pin.
+fn candidates(
+    (index, text, style, pin): (usize, &str, Style, Pin),
+    shape: Shape,
+    row: usize,
+) -> Vec<Result<Balloon, String>> {
+    // A side picks the shift by itself, so it takes a single attempt
+    let most = shape.max_shift(text, index);
+    let shifts: Vec<usize> = match (pin.shift, pin.side) {
+        (Some(shift), _) => vec![shift],
+        (None, Some(Side::Left)) => vec![most],
+        (None, Some(Side::Right)) => vec![0],
+        (None, None) => (0..=most).rev().collect(),
+    };
+    shifts
+        .into_iter()
+        .map(|shift| {
+            let owned = text.to_string();
+            let attempt = match (shape, pin.side) {
+                (Shape::Compact, _) => Balloon::compact(style.color, owned, index, row, shift),
+                (Shape::Boxed, Some(Side::Left)) => Balloon::left(style.color, owned, index, row),
+                (Shape::Boxed, Some(Side::Right)) => Balloon::right(style.color, owned, index, row),
+                (Shape::Boxed, None) => Balloon::new(style.color, owned, index, row, shift),
+            };
+            attempt.map(|balloon| balloon.with_style(style))
+        })
+        .collect()
+}
+
+/// Whether a label still to be placed fits in a row below the given one, clear of all arrows.
+fn fits_below(
+    grid: &[Vec<Balloon>],
+    balloon: (usize, &str, Style, Pin),
+    shape: Shape,
+    row: usize,
+) -> bool {
+    let rows = match balloon.3.row {
+        Some(pinned) => pinned..(pinned + 1).min(row),
+        None => 0..row,
+    };
+    rows.into_iter().any(|row| {
+        candidates(balloon, shape, row)
+            .into_iter()
             .flatten()
-            .fold(Template::default(), |template, balloon| {
-                template.overlay(balloon.pre_render())
+            .any(|candidate| {
+                obstacle(grid, &candidate, row, (&[], shape), Clearance::Arrows).is_none()
+            })
+    })
+}
+
+/// Puts a balloon in the first row where it fits, within the limits of its pin. It tries to keep
+/// clear of all arrows first, and only lets arrows cross it where they still show when there is
+/// no other way. Returns whether it found a row at all, and logs the attempts with the clearance
+/// that decided, or with the last one.
+fn place(
+    grid: &mut Vec<Vec<Balloon>>,
+    (index, text, style, pin): (usize, &str, Style, Pin),
+    shape: Shape,
+    rows: usize,
+    pending: &[(usize, String, Style, Pin)],
+    log: &mut Vec<String>,
+) -> bool {
+    let rows = match pin.row {
+        Some(row) => row..(row + 1).min(rows),
+        None => 0..rows,
+    };
+    let mut attempts = Vec::new();
+    let clearances = [
+        Clearance::Pending,
+        Clearance::Arrows,
+        Clearance::Overlap,
+        Clearance::Text,
+    ];
+    for clearance in clearances {
+        attempts.clear();
+        for row in rows.clone() {
+            if grid.len() <= row {
+                grid.resize_with(row + 1, Vec::new);
+            }
+            for attempt in candidates((index, text, style, pin), shape, row) {
+                let balloon = match attempt {
+                    Ok(balloon) => balloon,
+                    Err(error) => {
+                        attempts.push(format!("{:02} {}: row {}: {}", index, text, row, error));
+                        continue;
+                    }
+                };
+                let tried = format!(
+                    "{:02} {}: row {}, shift {}, columns {}-{}, arrow {}",
+                    index,
+                    text,
+                    row,
+                    balloon.shift(),
+                    balloon.start(),
+                    balloon.end(),
+                    balloon.arrow()
+                );
+                match obstacle(grid, &balloon, row, (pending, shape), clearance) {
+                    Some(obstacle) => attempts.push(format!("{}: {}", tried, obstacle)),
+                    None => {
+                        let crossing = match clearance {
+                            Clearance::Text => ", where arrows cross its text",
+                            Clearance::Overlap => ", where arrows cross it",
+                            Clearance::Arrows | Clearance::Pending => "",
+                        };
+                        log.append(&mut attempts);
+                        log.push(format!("{}: placed{}", tried, crossing));
+                        grid[row].push(balloon);
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    log.append(&mut attempts);
+    log.push(format!("{:02} {}: no row left, not drawn", index, text));
+    false
+}
+
+/// Checks that every pin can be honoured, in port order, before any balloon that isn't pinned is
+/// placed.
+fn check_pins(slots: &[Slot], labels: LabelStyle, rows: usize) -> Result<(), String> {
+    let mut problems = Vec::new();
+    let mut grid = Vec::new();
+    for (port, slot) in slots.iter().enumerate() {
+        let (pin, Some(text)) = (slot.pin(), slot.label()) else {
+            continue;
+        };
+        if pin.is_empty() {
+            continue;
+        }
+        let Some(shape) = labels.shape() else {
+            problems.push(format!(
+                "Port {:02} is pinned, but vertical labels always go straight above their jacks",
+                port
+            ));
+            continue;
+        };
+        if pin.shift.is_some() && pin.side.is_some() {
+            problems.push(format!("Port {:02} pins both a shift and a side", port));
+            continue;
+        }
+        if let Some(row) = pin.row.filter(|row| *row >= rows) {
+            problems.push(format!(
+                "Port {:02} is pinned to row {}, but there are only {} rows",
+                port, row, rows
+            ));
+            continue;
+        }
+        let most = shape.max_shift(text, port);
+        if let Some(shift) = pin.shift.filter(|shift| *shift > most) {
+            problems.push(format!(
+                "Port {:02} is pinned to shift {}, but its label reaches at most {}",
+                port, shift, most
+            ));
+            continue;
+        }
+        let balloon = (port, text, Color::NEUTRAL.into(), pin);
+        if !place(&mut grid, balloon, shape, rows, &[], &mut Vec::new()) {
+            problems.push(format!(
+                "The pinned balloon of port {:02} overlaps another pinned balloon",
+                port
+            ));
+        }
+    }
+    match problems.is_empty() {
+        true => Ok(()),
+        false => Err(problems.join("\n")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use indoc::indoc;
+
+    use crate::balloon::Balloon;
+    use crate::balloon::Shape;
+    use crate::panel::{
+        fit, marker, obstacle, overlay, stack, Clearance, Input, LabelStyle, Panel, MAX_ROWS,
+    };
+    use crate::placeholder::{Color, Style};
+    use crate::slot::Pin;
+    use crate::theme::GenericTheme;
+
+    /// Balloons of the texts on the ports from the first on, neutral and without pins.
+    fn balloons(texts: &[&str]) -> Vec<(usize, String, Style, Pin)> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(port, text)| {
+                (
+                    port,
+                    text.to_string(),
+                    Color::NEUTRAL.into(),
+                    Pin::default(),
+                )
             })
+            .collect()
+    }
+
+    #[test]
+    fn test_stack_log() {
+        let balloons = balloons(&["Television", "Doorbell"]);
+        let mut log = Vec::new();
+        let grid = stack(balloons, Shape::Boxed, MAX_ROWS, &mut log);
+        assert_eq!(vec![2], grid.iter().map(Vec::len).collect::<Vec<_>>());
+        assert_eq!(
+            vec![
+                "00 Television: row 0, shift 1, columns 6-22, arrow 16: placed",
+                "01 Doorbell: row 0, shift 1, columns 15-28, arrow 25: overlaps 00 Television at \
+                 columns 6-22",
+                "01 Doorbell: row 0, shift 0, columns 24-37, arrow 25: placed",
+            ],
+            log
+        );
+    }
+
+    #[test]
+    fn test_stack_compact() {
+        let balloons = balloons(&["Solaredge inverter", "Server"]);
+        let mut log = Vec::new();
+        let grid = stack(balloons, Shape::Compact, MAX_ROWS, &mut log);
+        assert_eq!(
+            "00 Solaredge inverter: row 0, shift 0, columns 16-38, arrow 16: covers the arrow of \
+             01 Server, which can't go in a row below",
+            log[0]
+        );
+        colored::control::set_override(false);
+        let rendered = overlay(&grid).render(&*GenericTheme::ASCII.build());
+        // A label wider than the pitch moves up a row, rather than hide the next arrow
+        assert_eq!(
+            vec![
+                "                ┌┬─ Solaredge inverter",
+                "                ││       ┌┬─ Server",
+            ],
+            rendered
+                .lines()
+                .skip_while(|line| line.is_empty())
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_demote() {
+        let balloons = balloons(&["Television", "Doorbell", "NAS"]);
+        let mut log = Vec::new();
+        let (grid, footnotes) = fit(balloons, Shape::Boxed, 1, None, true, &mut log);
+        assert_eq!(vec![3], grid.iter().map(Vec::len).collect::<Vec<_>>());
+        assert_eq!(vec!["¹ Television", "² Doorbell"], footnotes);
+        assert_eq!(
+            vec![
+                "2 of 3 labels fit, 00 Television becomes a footnote",
+                "2 of 3 labels fit, 01 Doorbell becomes a footnote",
+            ],
+            log[..2]
+        );
+        assert_eq!("¹²", marker(12));
+    }
+
+    #[test]
+    fn test_fit_hopeless() {
+        let pinned = Pin {
+            row: Some(2),
+            ..Pin::default()
+        };
+        let mut balloons = balloons(&["Television", "NAS"]);
+        balloons[0].3 = pinned;
+        let mut log = Vec::new();
+        let (grid, footnotes) = fit(balloons, Shape::Boxed, 2, None, true, &mut log);
+        // The television can never go in row 2 of 2, so the NAS keeps its label
+        assert_eq!(
+            vec!["NAS"],
+            grid.iter().flatten().map(Balloon::text).collect::<Vec<_>>()
+        );
+        assert!(footnotes.is_empty());
+        assert_eq!("00 Television: doesn't fit even alone, not drawn", log[0]);
+    }
+
+    #[test]
+    fn test_fit_abbreviations() {
+        let balloons = balloons(&["Television Living", "NAS"]);
+        let dictionary = BTreeMap::from([("Television".to_string(), "TV".to_string())]);
+        let mut log = Vec::new();
+        let (grid, footnotes) = fit(balloons, Shape::Boxed, 1, Some(&dictionary), true, &mut log);
+        // NAS can't get any shorter, so the television makes room
+        assert_eq!(
+            vec!["TV Living", "NAS"],
+            grid[0].iter().map(Balloon::text).collect::<Vec<_>>()
+        );
+        assert!(footnotes.is_empty());
+        assert_eq!(
+            "1 of 2 labels fit, 00 Television Living is shortened to TV Living",
+            log[0]
+        );
+    }
+
+    #[test]
+    fn test_obstacle() {
+        let balloon = |text: &str, port, row, shift| {
+            Balloon::new(Color::NEUTRAL, text.into(), port, row, shift).unwrap()
+        };
+        let nothing = (&[][..], Shape::Boxed);
+        // Columns 15-31, so the arrow of port 01 at 25 would go right through its text
+        let grid = vec![vec![balloon("Television", 0, 0, 0)], vec![]];
+        let doorbell = balloon("Doorbell", 1, 1, 1);
+        assert_eq!(
+            None,
+            obstacle(&grid, &doorbell, 1, nothing, Clearance::Text)
+        );
+        assert_eq!(
+            Some("its arrow crosses 00 Television".to_string()),
+            obstacle(&grid, &doorbell, 1, nothing, Clearance::Overlap)
+        );
+        assert_eq!(
+            Some("its arrow crosses 00 Television".to_string()),
+            obstacle(&grid, &doorbell, 1, nothing, Clearance::Arrows)
+        );
+        let grid = vec![vec![], vec![balloon("Doorbell", 1, 1, 0)]];
+        assert_eq!(
+            Some("covers the arrow of 01 Doorbell".to_string()),
+            obstacle(
+                &grid,
+                &balloon("Television", 0, 0, 0),
+                0,
+                nothing,
+                Clearance::Arrows
+            )
+        );
+
+        // A doorbell still to be placed can't go below the first row, but it can below the second
+        let pending = &balloons(&["", "Doorbell"])[1..];
+        assert_eq!(
+            Some("covers the arrow of 01 Doorbell, which can't go in a row below".to_string()),
+            obstacle(
+                &[vec![]],
+                &balloon("Television", 0, 0, 0),
+                0,
+                (pending, Shape::Boxed),
+                Clearance::Pending
+            )
+        );
+        assert_eq!(
+            None,
+            obstacle(
+                &[vec![], vec![]],
+                &balloon("Television", 0, 1, 0),
+                1,
+                (pending, Shape::Boxed),
+                Clearance::Pending
+            )
+        );
+    }
+
+    #[test]
+    fn test_crossing() {
+        let balloon = |text: &str, port, row, shift| {
+            Balloon::new(Color::NEUTRAL, text.into(), port, row, shift).unwrap()
+        };
+        let grid = vec![
+            vec![balloon("Television", 0, 0, 0)],
+            vec![balloon("Doorbell", 1, 1, 1)],
+        ];
+        colored::control::set_override(false);
+        let rendered = overlay(&grid).render(&*GenericTheme::ASCII.build());
+        let lines: Vec<&str> = rendered
+            .lines()
+            .skip_while(|line| line.is_empty())
+            .collect();
+        // The arrow of the doorbell goes on through the television, over its text
+        assert_eq!(
+            vec![
+                "               ┌───────────┐",
+                "               │ Doorbell  │",
+                "               └─────────┐┌┘",
+                "               ┌─────────┼┼───┐",
+                "               │  Televis││n  │",
+                "               └┐┌───────┼┼───┘",
+            ],
+            lines
+                .iter()
+                .take(6)
+                .map(|line| line.trim_end())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_check_pins() {
+        let input: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: Television
+                group: Living
+                pin:
+                  row: 1
+                  side: right
+              - !Occupied
+                text: Doorbell
+                group: Hall
+                pin:
+                  row: 1
+                  shift: 1
+              - !Occupied
+                text: NAS
+                group: Office
+                pin:
+                  row: 7
+              - !Reserved
+                owner: Jan
+                pin:
+                  shift: 0
+                  side: left
+        "})
+        .unwrap();
+        assert_eq!(
+            indoc! {"
+                The pinned balloon of port 01 overlaps another pinned balloon
+                Port 02 is pinned to row 7, but there are only 6 rows
+                Port 03 pins both a shift and a side"},
+            input.validate().unwrap_err()
+        );
+
+        let input: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            slots:
+              - !Occupied
+                text: Entertainment center
+                group: Living
+                pin:
+                  shift: 1
+        "})
+        .unwrap();
+        assert!(input.validate().is_ok());
+        let mut panel = Panel::from(input);
+        // A compact label of the first port has no room on the left of its arrow
+        panel.set_labels(LabelStyle::Compact);
+        assert_eq!(
+            Err("Port 00 is pinned to shift 1, but its label reaches at most 0".into()),
+            panel.validate()
+        );
+        panel.set_labels(LabelStyle::Vertical);
+        assert_eq!(
+            Err(
+                "Port 00 is pinned, but vertical labels always go straight above their jacks"
+                    .into()
+            ),
+            panel.validate()
+        );
+
+        let input: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            rows: 2
+            slots:
+              - !Occupied
+                text: Television
+                group: Living
+                pin:
+                  row: 2
+        "})
+        .unwrap();
+        assert_eq!(
+            Err("Port 00 is pinned to row 2, but there are only 2 rows".into()),
+            input.validate()
+        );
+        let mut panel = Panel::from(input);
+        panel.set_rows(3);
+        assert_eq!(Ok(()), panel.validate());
     }
 }
@@ -7,6 +7,61 @@ pub(crate) struct Color {
     pub(crate) blue: u8,
 }
 
+impl Color {
+    /// For balloons that don't belong to a group
+    pub(crate) const NEUTRAL: Color = Color {
+        red: 192,
+        green: 192,
+        blue: 192,
+    };
+}
+
+/// Whether a balloon describes what is patched, or what is only reserved or planned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Default)]
+pub(crate) enum Tone {
+    #[default]
+    Firm,
+    Tentative,
+}
+
+/// How much attention a balloon should draw.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Default)]
+pub(crate) enum Emphasis {
+    #[default]
+    Normal,
+    /// Doesn't match the filter, or isn't highlighted
+    Muted,
+    Highlighted,
+}
+
+/// Everything about the looks of a balloon, except its shape.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) struct Style {
+    pub(crate) color: Color,
+    pub(crate) tone: Tone,
+    pub(crate) emphasis: Emphasis,
+}
+
+impl From<Color> for Style {
+    fn from(color: Color) -> Self {
+        Style {
+            color,
+            tone: Tone::default(),
+            emphasis: Emphasis::default(),
+        }
+    }
+}
+
+impl From<Color> for colored::Color {
+    fn from(color: Color) -> Self {
+        colored::Color::TrueColor {
+            r: color.red,
+            g: color.green,
+            b: color.blue,
+        }
+    }
+}
+
 ///
 /// All of these are theme specific, except for text.
 /// The order determines which one should be drawn in case of overlap.
@@ -16,6 +71,7 @@ pub(crate) struct Color {
 ///  - All except none go over Arrows
 ///
 /// For overlap of South or North and arrow, specific behavior is implemented.
+/// Everything but empty space carries the style of its balloon.
 ///
 #[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Debug, Default)]
 pub(crate) enum PlaceHolder {
@@ -27,51 +83,140 @@ pub(crate) enum PlaceHolder {
     Padding,
 
     /// Arrow sides
-    ArrowLeft,
-    ArrowRight,
+    ArrowLeft(Style),
+    ArrowRight(Style),
 
     /// Balloon sides
-    North,
-    East,
-    South,
-    West,
+    North(Style),
+    East(Style),
+    South(Style),
+    West(Style),
 
     /// Balloon corners
-    NorthEast,
-    SouthEast,
-    SouthWest,
-    NorthWest,
+    NorthEast(Style),
+    SouthEast(Style),
+    SouthWest(Style),
+    NorthWest(Style),
 
     /// Balloon transitions (between balloon and arrow)
-    TransitionLeft,
-    TransitionRight,
+    TransitionLeft(Style),
+    TransitionRight(Style),
 
     /// Balloon transition edges (between balloon and arrow)
-    TransitionLeftEdge,
-    TransitionRightEdge,
+    #[allow(dead_code)]
+    TransitionLeftEdge(Style),
+    TransitionRightEdge(Style),
+
+    /// The line from a compact label to its arrow, and where it meets the arrow
+    Leader(Style),
+    LeaderJoint(Style),
 
     /// Overlays between arrow and balloon
-    ArrowOverlayNorthLeft,
-    ArrowOverlayNorthRight,
-    ArrowOverlaySouthLeft,
-    ArrowOverlaySouthRight,
+    ArrowOverlayNorthLeft(Style),
+    ArrowOverlayNorthRight(Style),
+    ArrowOverlaySouthLeft(Style),
+    ArrowOverlaySouthRight(Style),
 
     /// Text
-    Text(char, Color),
+    Text(char, Style),
 }
 
 impl PlaceHolder {
+    /// The looks of the balloon this belongs to, if any.
+    pub(crate) fn style(&self) -> Option<Style> {
+        match *self {
+            PlaceHolder::None | PlaceHolder::Padding => None,
+            PlaceHolder::ArrowLeft(style)
+            | PlaceHolder::ArrowRight(style)
+            | PlaceHolder::North(style)
+            | PlaceHolder::East(style)
+            | PlaceHolder::South(style)
+            | PlaceHolder::West(style)
+            | PlaceHolder::NorthEast(style)
+            | PlaceHolder::SouthEast(style)
+            | PlaceHolder::SouthWest(style)
+            | PlaceHolder::NorthWest(style)
+            | PlaceHolder::TransitionLeft(style)
+            | PlaceHolder::TransitionRight(style)
+            | PlaceHolder::TransitionLeftEdge(style)
+            | PlaceHolder::TransitionRightEdge(style)
+            | PlaceHolder::Leader(style)
+            | PlaceHolder::LeaderJoint(style)
+            | PlaceHolder::ArrowOverlayNorthLeft(style)
+            | PlaceHolder::ArrowOverlayNorthRight(style)
+            | PlaceHolder::ArrowOverlaySouthLeft(style)
+            | PlaceHolder::ArrowOverlaySouthRight(style)
+            | PlaceHolder::Text(_, style) => Some(style),
+        }
+    }
+
+    /// A character that tells the placeholders apart in a dump: `.` for empty space, `_` for
+    /// padding, `(` and `)` for arrows, `n`, `e`, `s` and `w` for sides, `p`, `q`, `b` and `d`
+    /// for corners (shaped like them), `t`, `T`, `k` and `K` for transitions and their edges, `l`
+    /// and `j` for leaders and their joints, `u`, `U`, `v` and `V` for arrows crossing north and south sides, and `#` for text.
+    pub(crate) fn code(&self) -> char {
+        match self {
+            PlaceHolder::None => '.',
+            PlaceHolder::Padding => '_',
+            PlaceHolder::ArrowLeft(_) => '(',
+            PlaceHolder::ArrowRight(_) => ')',
+            PlaceHolder::North(_) => 'n',
+            PlaceHolder::East(_) => 'e',
+            PlaceHolder::South(_) => 's',
+            PlaceHolder::West(_) => 'w',
+            PlaceHolder::NorthWest(_) => 'p',
+            PlaceHolder::NorthEast(_) => 'q',
+            PlaceHolder::SouthWest(_) => 'b',
+            PlaceHolder::SouthEast(_) => 'd',
+            PlaceHolder::TransitionLeft(_) => 't',
+            PlaceHolder::TransitionRight(_) => 'T',
+            PlaceHolder::TransitionLeftEdge(_) => 'k',
+            PlaceHolder::TransitionRightEdge(_) => 'K',
+            PlaceHolder::Leader(_) => 'l',
+            PlaceHolder::LeaderJoint(_) => 'j',
+            PlaceHolder::ArrowOverlayNorthLeft(_) => 'u',
+            PlaceHolder::ArrowOverlayNorthRight(_) => 'U',
+            PlaceHolder::ArrowOverlaySouthLeft(_) => 'v',
+            PlaceHolder::ArrowOverlaySouthRight(_) => 'V',
+            PlaceHolder::Text(..) => '#',
+        }
+    }
+
+    /// Where an arrow crosses a balloon, the crossing takes the style of the arrow, so a leader
+    /// line can be followed all the way to its jack.
     pub(crate) fn overlay(&self, another: &PlaceHolder) -> PlaceHolder {
         let front = max(self, another);
         let back = min(self, another);
 
         match [front, back] {
-            [PlaceHolder::North, PlaceHolder::ArrowLeft] => PlaceHolder::ArrowOverlayNorthLeft,
-            [PlaceHolder::North, PlaceHolder::ArrowRight] => PlaceHolder::ArrowOverlayNorthRight,
-            [PlaceHolder::South, PlaceHolder::ArrowLeft] => PlaceHolder::ArrowOverlaySouthLeft,
-            [PlaceHolder::South, PlaceHolder::ArrowRight] => PlaceHolder::ArrowOverlaySouthRight,
+            [PlaceHolder::North(_), PlaceHolder::ArrowLeft(style)] => {
+                PlaceHolder::ArrowOverlayNorthLeft(*style)
+            }
+            [PlaceHolder::North(_), PlaceHolder::ArrowRight(style)] => {
+                PlaceHolder::ArrowOverlayNorthRight(*style)
+            }
+            [PlaceHolder::South(_), PlaceHolder::ArrowLeft(style)] => {
+                PlaceHolder::ArrowOverlaySouthLeft(*style)
+            }
+            [PlaceHolder::South(_), PlaceHolder::ArrowRight(style)] => {
+                PlaceHolder::ArrowOverlaySouthRight(*style)
+            }
+            // A leader is crossed like the bottom of a balloon
+            [PlaceHolder::Leader(_), PlaceHolder::ArrowLeft(style)] => {
+                PlaceHolder::ArrowOverlaySouthLeft(*style)
+            }
+            [PlaceHolder::Leader(_), PlaceHolder::ArrowRight(style)] => {
+                PlaceHolder::ArrowOverlaySouthRight(*style)
+            }
             [PlaceHolder::None, _] => *back,
             [_, PlaceHolder::None] => *front,
+            // Within a balloon an arrow goes on over the text, rather than seem to end there
+            [PlaceHolder::ArrowLeft(_) | PlaceHolder::ArrowRight(_), PlaceHolder::Padding] => {
+                *front
+            }
+            [PlaceHolder::Text(..), PlaceHolder::ArrowLeft(_) | PlaceHolder::ArrowRight(_)] => {
+                *back
+            }
             [PlaceHolder::Padding, _] => *front,
             [_, PlaceHolder::Padding] => *back,
             _ => *front,
@@ -0,0 +1,230 @@
+use itertools::Itertools;
+
+use crate::edit::Edit;
+use crate::panel::Panel;
+use crate::slot::Slot;
+
+/// The moves that give every group a block of neighbouring ports, in a preferred order.
+///
+/// Reserved, faulty and disabled ports stay where they are, so a block may have such a port in
+/// the middle. Of all the ways to lay out the blocks, the plan takes the one that moves the
+/// fewest cables.
+pub struct Plan {
+    pub(crate) steps: Vec<Edit>,
+}
+
+impl Plan {
+    /// Plans the moves for the slots of a panel. Groups missing from the order go after the
+    /// others, in the order of their first port.
+    pub fn new(panel: &Panel, order: &[String]) -> Result<Self, String> {
+        let slots = &panel.slots;
+        for group in order {
+            if !slots.iter().any(|slot| slot.group() == Some(group)) {
+                return Err(format!("Group {} is not on the panel", group));
+            }
+        }
+        let groups: Vec<&str> = order
+            .iter()
+            .map(String::as_str)
+            .chain(slots.iter().filter_map(Slot::group))
+            .unique()
+            .collect();
+        let available: Vec<usize> = (0..slots.len())
+            .filter(|port| movable(&slots[*port]))
+            .collect();
+        let target = layout(slots, &groups, &available);
+        Ok(Self {
+            steps: steps(slots, target),
+        })
+    }
+
+    /// Lists the steps, numbered, with the label of each cable that moves.
+    pub fn describe(&self, panel: &Panel) -> String {
+        let mut slots = panel.slots.clone();
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let label = |port: &usize| slots[*port].label().unwrap_or_default().to_string();
+                let line = match step {
+                    Edit::Move { from, to } => {
+                        format!("Move {:02} to {:02} ({})", from, to, label(from))
+                    }
+                    Edit::Swap { a, b } => {
+                        format!("Swap {:02} and {:02} ({}, {})", a, b, label(a), label(b))
+                    }
+                    Edit::Set { .. } | Edit::Free { .. } => unreachable!("Plans only move"),
+                };
+                step.apply(&mut slots)
+                    .expect("Steps are planned on these slots");
+                format!("{:>3}. {}", index + 1, line)
+            })
+            .join("\n")
+    }
+
+    /// The panel after all the steps.
+    pub fn apply(&self, mut panel: Panel) -> Panel {
+        for step in &self.steps {
+            step.apply(&mut panel.slots)
+                .expect("Steps are planned on these slots");
+        }
+        panel
+    }
+}
+
+/// Whether a port may hold a cable in the plan: it is free or has one already.
+fn movable(slot: &Slot) -> bool {
+    matches!(
+        slot,
+        Slot::Free | Slot::Occupied { .. } | Slot::Planned { .. }
+    )
+}
+
+/// The port every cable goes to, as pairs of its current and its new port.
+///
+/// The blocks are laid out over the available ports in order, with the free ports before, between
+/// or after them, however moves the fewest cables. Within its block, a cable that is already there
+/// stays, and the others fill the remaining ports in order.
+fn layout(slots: &[Slot], groups: &[&str], available: &[usize]) -> Vec<(usize, usize)> {
+    let sizes: Vec<usize> = groups
+        .iter()
+        .map(|group| {
+            slots
+                .iter()
+                .filter(|slot| slot.group() == Some(group))
+                .count()
+        })
+        .collect();
+    let hits = |group: usize, start: usize| {
+        available[start..start + sizes[group]]
+            .iter()
+            .filter(|port| slots[**port].group() == Some(groups[group]))
+            .count()
+    };
+
+    // The fewest moves for the blocks from a group on, starting at an available port
+    let length = available.len();
+    let mut best = vec![vec![(0, 0); length + 1]; groups.len() + 1];
+    for group in (0..groups.len()).rev() {
+        let rest: usize = sizes[group + 1..].iter().sum();
+        for from in (0..=length).rev() {
+            best[group][from] = (usize::MAX, 0);
+            let last = (length + 1).saturating_sub(sizes[group] + rest);
+            for start in from..last {
+                let moves =
+                    sizes[group] - hits(group, start) + best[group + 1][start + sizes[group]].0;
+                if moves < best[group][from].0 {
+                    best[group][from] = (moves, start);
+                }
+            }
+        }
+    }
+
+    let mut targets = Vec::new();
+    let mut from = 0;
+    for (group, name) in groups.iter().enumerate() {
+        let start = best[group][from].1;
+        let block = &available[start..start + sizes[group]];
+        let (staying, moving): (Vec<usize>, Vec<usize>) = (0..slots.len())
+            .filter(|port| slots[*port].group() == Some(name))
+            .partition(|port| block.contains(port));
+        let open = block.iter().filter(|port| !staying.contains(port));
+        targets.extend(moving.into_iter().zip(open.copied()));
+        from = start + sizes[group];
+    }
+    targets
+}
+
+/// Orders the moves so each goes to a port that is free by then. When cables wait on each other
+/// in a circle, one of them goes to a free port out of the way first, or they are swapped if there
+/// is no free port.
+fn steps(slots: &[Slot], targets: Vec<(usize, usize)>) -> Vec<Edit> {
+    let mut slots = slots.to_vec();
+    let mut pending = targets;
+    let mut steps = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|(_, to)| slots[*to] == Slot::Free);
+        let step = match ready {
+            Some(index) => {
+                let (from, to) = pending.remove(index);
+                Edit::Move { from, to }
+            }
+            None => {
+                let (from, to) = pending[0];
+                match slots.iter().position(|slot| *slot == Slot::Free) {
+                    Some(scratch) => {
+                        pending[0].0 = scratch;
+                        Edit::Move { from, to: scratch }
+                    }
+                    None => {
+                        pending.remove(0);
+                        // The cable that was in the way now waits where this one was
+                        for waiting in pending.iter_mut().filter(|(other, _)| *other == to) {
+                            waiting.0 = from;
+                        }
+                        pending.retain(|(from, to)| from != to);
+                        Edit::Swap { a: from, b: to }
+                    }
+                }
+            }
+        };
+        step.apply(&mut slots)
+            .expect("Moves go to free ports, swaps between cables");
+        steps.push(step);
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit::Edit;
+    use crate::panel::{Input, Panel};
+    use crate::plan::Plan;
+    use crate::slot::Slot;
+
+    fn panel(groups: &[Option<&str>]) -> Panel {
+        let slots = groups
+            .iter()
+            .enumerate()
+            .map(|(port, group)| match group {
+                Some(group) => Slot::occupied(format!("Host {}", port), *group),
+                None => Slot::Free,
+            })
+            .collect();
+        let input: Input = serde_yaml::from_str("theme: ASCII\nslots: []").unwrap();
+        Panel::from(Input { slots, ..input })
+    }
+
+    fn groups(panel: &Panel) -> Vec<Option<&str>> {
+        panel.slots.iter().map(Slot::group).collect()
+    }
+
+    #[test]
+    fn test_plan() {
+        let before = panel(&[Some("A"), Some("B"), Some("A"), None, Some("B")]);
+        let plan = Plan::new(&before, &[]).unwrap();
+        // The B in the way goes to the free port first
+        assert!(matches!(
+            plan.steps[..],
+            [Edit::Move { from: 1, to: 3 }, Edit::Move { from: 2, to: 1 }]
+        ));
+        let after = plan.apply(before);
+        assert_eq!(
+            vec![Some("A"), Some("A"), None, Some("B"), Some("B")],
+            groups(&after)
+        );
+        assert!(Plan::new(&after, &[]).unwrap().steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_without_free_ports() {
+        let before = panel(&[Some("A"), Some("B"), Some("A")]);
+        let plan = Plan::new(&before, &["B".into()]).unwrap();
+        assert!(matches!(plan.steps[..], [Edit::Swap { .. }]));
+        assert_eq!(
+            vec![Some("B"), Some("A"), Some("A")],
+            groups(&plan.apply(before))
+        );
+        assert!(Plan::new(&panel(&[Some("A")]), &["C".into()]).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::format::Format;
+use crate::inventory::Inventory;
+use crate::panel::{Input, Panel};
+use crate::switch::{Switch, SwitchInput};
+use crate::theme::visible_width;
+
+/// A device in a rack, mounted at its lowest rack unit.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum Device {
+    /// A 1U patch panel, described inline or in its own inventory file
+    Panel {
+        unit: u32,
+        /// Relative to the rack file
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        panel: Option<Input>,
+    },
+    /// A 1U switch, described inline or in its own file
+    Switch {
+        unit: u32,
+        /// Relative to the rack file
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        switch: Option<SwitchInput>,
+    },
+    /// Anything else, like a shelf or a UPS, drawn as a labelled box
+    Block {
+        unit: u32,
+        #[serde(default = "one")]
+        height: u32,
+        label: String,
+    },
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Device {
+    fn unit(&self) -> u32 {
+        match self {
+            Device::Panel { unit, .. }
+            | Device::Switch { unit, .. }
+            | Device::Block { unit, .. } => *unit,
+        }
+    }
+
+    fn height(&self) -> u32 {
+        match self {
+            Device::Panel { .. } | Device::Switch { .. } => 1,
+            Device::Block { height, .. } => *height,
+        }
+    }
+}
+
+/// A rack as described in a file, listing its devices by rack unit.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct RackInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    /// The height of the rack
+    pub(crate) units: u32,
+    pub(crate) devices: Vec<Device>,
+}
+
+impl RackInput {
+    /// Checks that every device fits in the rack without overlapping another one.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+        let mut taken: Vec<Option<usize>> = vec![None; self.units as usize];
+        for (index, device) in self.devices.iter().enumerate() {
+            if device.unit() == 0 {
+                problems.push(format!(
+                    "Device {} is at U0, but rack units count from U1",
+                    index + 1
+                ));
+            }
+            if device.height() == 0 {
+                problems.push(format!("Device {} takes no rack units", index + 1));
+            }
+            if device.unit() == 0 || device.height() == 0 {
+                continue;
+            }
+            let (bottom, top) = (device.unit(), device.unit() + device.height() - 1);
+            if top > self.units {
+                problems.push(format!(
+                    "Device {} at U{} doesn't fit in a rack of {} units",
+                    index + 1,
+                    bottom,
+                    self.units
+                ));
+                continue;
+            }
+            for unit in bottom..=top {
+                match taken[unit as usize - 1] {
+                    Some(other) => problems.push(format!(
+                        "Devices {} and {} both take U{}",
+                        other + 1,
+                        index + 1,
+                        unit
+                    )),
+                    None => taken[unit as usize - 1] = Some(index),
+                }
+            }
+            let sources = match device {
+                Device::Panel { file, panel, .. } => Some(("Panel", file, panel.is_some())),
+                Device::Switch { file, switch, .. } => Some(("Switch", file, switch.is_some())),
+                Device::Block { .. } => None,
+            };
+            if let Some((kind, file, inline)) = sources {
+                if file.is_some() == inline {
+                    problems.push(format!(
+                        "{} at U{} needs either a file or an inline {}",
+                        kind,
+                        bottom,
+                        kind.to_lowercase()
+                    ));
+                }
+            }
+        }
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems.join("\n")),
+        }
+    }
+}
+
+enum Mounted {
+    Panel(Box<Panel>),
+    Switch(Box<Switch>),
+    Block(String),
+}
+
+/// A rack with the panels of all its devices loaded, ready to be drawn.
+pub struct Rack {
+    name: Option<String>,
+    units: u32,
+    /// The lowest unit, the height and the device, from the top of the rack down
+    devices: Vec<(u32, u32, Mounted)>,
+}
+
+impl Rack {
+    /// The width of blocks in a rack without panels
+    const MIN_WIDTH: usize = 40;
+
+    /// Opens a rack file, and the inventory files of its panels next to it.
+    pub fn open(path: impl AsRef<Path>, format: Option<Format>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let format = format.unwrap_or(Format::detect(path));
+        let source =
+            read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+        let input: RackInput = format
+            .parse(&source)
+            .with_context(|| format!("Could not parse {} as {}", path.display(), format))?;
+        input.validate().map_err(|error| anyhow!(error))?;
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut devices = Vec::new();
+        for device in input.devices {
+            let (unit, height) = (device.unit(), device.height());
+            let mounted = match device {
+                Device::Panel {
+                    file: Some(file), ..
+                } => Mounted::Panel(Box::new(
+                    Inventory::open(directory.join(file), None)?.panel(),
+                )),
+                Device::Panel {
+                    panel: Some(panel), ..
+                } => {
+                    panel.validate().map_err(|error| anyhow!(error))?;
+                    Mounted::Panel(Box::new(panel.into()))
+                }
+                Device::Switch {
+                    file: Some(file), ..
+                } => Mounted::Switch(Box::new(Switch::open(directory.join(file), None)?)),
+                Device::Switch {
+                    switch: Some(switch),
+                    ..
+                } => {
+                    switch.validate().map_err(|error| anyhow!(error))?;
+                    Mounted::Switch(Box::new(switch.into()))
+                }
+                Device::Panel { .. } | Device::Switch { .. } => {
+                    unreachable!("Checked by validate")
+                }
+                Device::Block { label, .. } => Mounted::Block(label),
+            };
+            devices.push((unit, height, mounted));
+        }
+        devices.sort_by_key(|(unit, _, _)| std::cmp::Reverse(*unit));
+        Ok(Self {
+            name: input.name,
+            units: input.units,
+            devices,
+        })
+    }
+
+    /// Changes every panel in the rack, like to apply the same view options to all of them.
+    pub fn map_panels(
+        mut self,
+        change: impl Fn(Panel) -> anyhow::Result<Panel>,
+    ) -> anyhow::Result<Self> {
+        self.devices = self
+            .devices
+            .into_iter()
+            .map(|(unit, height, mounted)| {
+                Ok(match mounted {
+                    Mounted::Panel(panel) => {
+                        (unit, height, Mounted::Panel(Box::new(change(*panel)?)))
+                    }
+                    block => (unit, height, block),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(self)
+    }
+
+    /// Draws the devices from the top of the rack down, with the rack units on the left.
+    ///
+    /// The balloons of a panel go in the empty units above it, and only take extra lines when
+    /// those run out.
+    pub fn render(&mut self) -> String {
+        // What goes above the front of a device, the front itself and what hangs below it
+        let layers: Vec<Option<(String, String, String)>> = self
+            .devices
+            .iter_mut()
+            .map(|(_, _, mounted)| match mounted {
+                Mounted::Panel(panel) => {
+                    let (balloons, frame) = panel.render_layers();
+                    Some((balloons, frame, String::new()))
+                }
+                Mounted::Switch(switch) => Some(switch.render_layers()),
+                Mounted::Block(_) => None,
+            })
+            .collect();
+        let width = layers
+            .iter()
+            .flatten()
+            .filter_map(|(_, frame, _)| frame.lines().map(visible_width).max())
+            .max()
+            .unwrap_or(Self::MIN_WIDTH);
+
+        let mut lines: Vec<(Option<u32>, String)> = Vec::new();
+        let mut top = self.units;
+        for ((unit, height, mounted), layers) in self.devices.iter().zip(layers) {
+            let empty: Vec<u32> = (unit + height..=top).rev().collect();
+            let (balloons, frame, below): (Vec<String>, Vec<String>, Vec<String>) =
+                match (mounted, layers) {
+                    (Mounted::Block(label), _) => (vec![], block(label, *height, width), vec![]),
+                    (_, Some((balloons, frame, below))) => (
+                        balloons
+                            .lines()
+                            .filter(|line| !line.trim().is_empty())
+                            .map(String::from)
+                            .collect(),
+                        frame.lines().map(String::from).collect(),
+                        below
+                            .lines()
+                            .filter(|line| !line.trim().is_empty())
+                            .map(String::from)
+                            .collect(),
+                    ),
+                    (_, None) => unreachable!("Panels and switches have layers"),
+                };
+            lines.extend(gap(&empty, balloons));
+
+            let labelled: Vec<usize> = match mounted {
+                // Next to the middle of the jacks
+                Mounted::Panel(_) | Mounted::Switch(_) => vec![(frame.len() - 1) / 2],
+                Mounted::Block(_) => (1..=*height as usize).collect(),
+            };
+            let mut units = (*unit..unit + height).rev();
+            lines.extend(frame.into_iter().enumerate().map(|(index, line)| {
+                let unit = labelled.contains(&index).then(|| units.next()).flatten();
+                (unit, line)
+            }));
+            lines.extend(below.into_iter().map(|line| (None, line)));
+            top = unit - 1;
+        }
+        lines.extend((1..=top).rev().map(|unit| (Some(unit), String::new())));
+
+        let gutter = format!("U{}", self.units).len();
+        self.name
+            .iter()
+            .cloned()
+            .chain(lines.into_iter().map(|(unit, line)| {
+                let label = unit.map(|unit| format!("U{}", unit)).unwrap_or_default();
+                format!("{:>gutter$}  {}", label, line, gutter = gutter)
+                    .trim_end()
+                    .to_string()
+            }))
+            .intersperse("\n".into())
+            .collect()
+    }
+}
+
+/// The lines between two devices: balloons at the bottom, next to as many empty units as there are.
+fn gap(empty: &[u32], balloons: Vec<String>) -> Vec<(Option<u32>, String)> {
+    let lines = empty.len().max(balloons.len());
+    let (unlabelled, blank) = (lines - empty.len(), lines - balloons.len());
+    (0..lines)
+        .map(|line| {
+            let unit = line.checked_sub(unlabelled).map(|index| empty[index]);
+            let balloon = line
+                .checked_sub(blank)
+                .map(|index| balloons[index].clone())
+                .unwrap_or_default();
+            (unit, balloon)
+        })
+        .collect()
+}
+
+/// Draws a device that isn't a panel as a box with its label on the first line.
+fn block(label: &str, height: u32, width: usize) -> Vec<String> {
+    let inner = width.max(visible_width(label) + 4) - 2;
+    [format!("┌{}┐", "─".repeat(inner))]
+        .into_iter()
+        .chain((0..height).map(|line| {
+            let text = if line == 0 { label } else { "" };
+            format!("│{:^inner$}│", text, inner = inner)
+        }))
+        .chain([format!("└{}┘", "─".repeat(inner))])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::rack::{block, gap, RackInput};
+
+    #[test]
+    fn test_validate() {
+        let rack: RackInput = serde_yaml::from_str(indoc! {"
+            units: 4
+            devices:
+              - !Block
+                unit: 1
+                height: 2
+                label: Shelf
+              - !Block
+                unit: 2
+                label: Switch
+              - !Block
+                unit: 4
+                height: 2
+                label: UPS
+              - !Panel
+                unit: 3
+        "})
+        .unwrap();
+        assert_eq!(
+            indoc! {"
+                Devices 1 and 2 both take U2
+                Device 3 at U4 doesn't fit in a rack of 4 units
+                Panel at U3 needs either a file or an inline panel"},
+            rack.validate().unwrap_err()
+        );
+
+        let rack: RackInput = serde_yaml::from_str(indoc! {"
+            units: 4
+            devices:
+              - !Block
+                unit: 0
+                height: 0
+                label: Shelf
+              - !Block
+                unit: 2
+                height: 0
+                label: Switch
+        "})
+        .unwrap();
+        assert_eq!(
+            indoc! {"
+                Device 1 is at U0, but rack units count from U1
+                Device 1 takes no rack units
+                Device 2 takes no rack units"},
+            rack.validate().unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_gap() {
+        assert_eq!(
+            vec![
+                (None, "a".to_string()),
+                (Some(5), "b".to_string()),
+                (Some(4), "c".to_string())
+            ],
+            gap(&[5, 4], vec!["a".into(), "b".into(), "c".into()])
+        );
+        assert_eq!(
+            vec![(Some(5), "".to_string()), (Some(4), "a".to_string())],
+            gap(&[5, 4], vec!["a".into()])
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        assert_eq!(
+            vec!["┌────────┐", "│ Shelf  │", "│        │", "└────────┘"],
+            block("Shelf", 2, 10)
+        );
+    }
+}
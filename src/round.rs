@@ -1,5 +1,6 @@
 pub(crate) trait Round: Copy {
     fn up(self, multiple: Self) -> Self;
+    #[allow(dead_code)]
     fn down(self, multiple: Self) -> Self;
 }
 
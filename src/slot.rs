@@ -1,7 +1,186 @@
-use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 
-#[derive(Clone, Deserialize, Debug)]
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum Slot {
-    Occupied { text: String, group: String },
+    Occupied {
+        text: String,
+        group: String,
+        /// Free-form details, like the VLAN or the cable ID
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        tags: BTreeMap<String, String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        notes: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pin: Option<Pin>,
+    },
+    Free,
+    /// Kept free for someone, possibly until a given date
+    Reserved {
+        owner: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expiry: Option<NaiveDate>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pin: Option<Pin>,
+    },
+    /// Failed its cable test
+    Faulty,
+    /// Blanked off, there is no keystone
+    Disabled,
+    /// Going to be patched like this
+    Planned {
+        text: String,
+        group: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pin: Option<Pin>,
+    },
+}
+
+/// The side of its arrow a balloon reaches out to.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Where the balloon of a slot goes, when the automatic layout doesn't look right. Whatever is
+/// left out is still picked automatically.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Pin {
+    /// The row of balloons, counting up from the panel from 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) row: Option<usize>,
+    /// How many ports the balloon reaches out to the left of its arrow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) shift: Option<usize>,
+    /// All the way to the left or right of its arrow, instead of a shift
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) side: Option<Side>,
+}
+
+impl Pin {
+    pub fn is_empty(&self) -> bool {
+        self == &Pin::default()
+    }
+}
+
+/// The kind of a slot, without its details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum State {
+    Occupied,
     Free,
+    Reserved,
+    Faulty,
+    Disabled,
+    Planned,
+}
+
+impl State {
+    pub const ALL: [State; 6] = [
+        State::Occupied,
+        State::Free,
+        State::Reserved,
+        State::Faulty,
+        State::Disabled,
+        State::Planned,
+    ];
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            State::Occupied => "in use",
+            State::Free => "available",
+            State::Reserved => "kept free for someone",
+            State::Faulty => "failed cable test",
+            State::Disabled => "blanked off",
+            State::Planned => "to be patched",
+        }
+    }
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Occupied => write!(f, "occupied"),
+            State::Free => write!(f, "free"),
+            State::Reserved => write!(f, "reserved"),
+            State::Faulty => write!(f, "faulty"),
+            State::Disabled => write!(f, "disabled"),
+            State::Planned => write!(f, "planned"),
+        }
+    }
+}
+
+impl Slot {
+    pub fn occupied(text: impl Into<String>, group: impl Into<String>) -> Slot {
+        Slot::Occupied {
+            text: text.into(),
+            group: group.into(),
+            tags: BTreeMap::new(),
+            notes: None,
+            pin: None,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        match self {
+            Slot::Occupied { .. } => State::Occupied,
+            Slot::Free => State::Free,
+            Slot::Reserved { .. } => State::Reserved,
+            Slot::Faulty => State::Faulty,
+            Slot::Disabled => State::Disabled,
+            Slot::Planned { .. } => State::Planned,
+        }
+    }
+
+    /// The text of the balloon of this slot, if it gets one.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Slot::Occupied { text, .. } | Slot::Planned { text, .. } => Some(text),
+            Slot::Reserved { owner, .. } => Some(owner),
+            Slot::Free | Slot::Faulty | Slot::Disabled => None,
+        }
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        match self {
+            Slot::Occupied { group, .. } | Slot::Planned { group, .. } => Some(group),
+            _ => None,
+        }
+    }
+
+    pub fn tags(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            Slot::Occupied { tags, .. } => Some(tags),
+            _ => None,
+        }
+    }
+
+    /// Looks up `label`, `group`, `state` or otherwise a tag of this slot.
+    pub fn field(&self, key: &str) -> Option<String> {
+        match key {
+            "label" => self.label().map(String::from),
+            "group" => self.group().map(String::from),
+            "state" => Some(self.state().to_string()),
+            _ => self.tags().and_then(|tags| tags.get(key)).cloned(),
+        }
+    }
+
+    /// Where the balloon of this slot is pinned, if anywhere.
+    pub fn pin(&self) -> Pin {
+        match self {
+            Slot::Occupied { pin, .. } | Slot::Reserved { pin, .. } | Slot::Planned { pin, .. } => {
+                pin.unwrap_or_default()
+            }
+            Slot::Free | Slot::Faulty | Slot::Disabled => Pin::default(),
+        }
+    }
+
+    /// Whether the balloon of this slot describes something that isn't patched (yet).
+    pub(crate) fn tentative(&self) -> bool {
+        matches!(self, Slot::Reserved { .. } | Slot::Planned { .. })
+    }
 }
@@ -0,0 +1,278 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::balloon::{Balloon, Shape};
+use crate::format::Format;
+use crate::metadata::Metadata;
+use crate::panel::arrange;
+use crate::placeholder::{Color, Emphasis, Style, Tone};
+use crate::slot::Pin;
+use crate::theme::{GenericTheme, Theme};
+
+/// The kinds of ports on the front of a switch.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum PortKind {
+    #[default]
+    Copper,
+    /// A cage for an SFP module
+    Sfp,
+    Console,
+    Management,
+}
+
+impl PortKind {
+    /// Whether the port counts towards the port numbers, like console ports don't.
+    fn numbered(&self) -> bool {
+        matches!(self, PortKind::Copper | PortKind::Sfp)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct SwitchPort {
+    #[serde(default)]
+    pub(crate) kind: PortKind,
+    /// The host on the other end of the cable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) vlan: Option<String>,
+}
+
+impl SwitchPort {
+    /// The text of the balloon of this port: the host, or otherwise the VLAN.
+    pub(crate) fn text(&self) -> Option<String> {
+        self.label
+            .clone()
+            .or(self.vlan.as_ref().map(|vlan| format!("VLAN {}", vlan)))
+    }
+
+    /// Picks the colour of the port, its group or otherwise its VLAN.
+    fn key(&self) -> Option<&str> {
+        self.group.as_deref().or(self.vlan.as_deref())
+    }
+}
+
+/// A switch as described in a file. Its ports are listed in pairs, top then bottom, from left to
+/// right, so the usual odd ports go on top.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SwitchInput {
+    pub(crate) theme: GenericTheme,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub(crate) metadata: Metadata,
+    pub(crate) ports: Vec<SwitchPort>,
+}
+
+impl SwitchInput {
+    /// Two ports for every column a panel has room for
+    const MAX_PORTS: usize = 2 * (Balloon::RANGE + 1);
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ports.len() > Self::MAX_PORTS {
+            return Err(format!(
+                "A switch holds at most {} ports, found {}",
+                Self::MAX_PORTS,
+                self.ports.len()
+            ));
+        }
+        for (position, port) in self.ports.iter().enumerate() {
+            if port.text().is_some_and(|text| text.trim().is_empty()) {
+                return Err(format!("Port {} has an empty label", position + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Switch {
+    pub(crate) ports: Vec<SwitchPort>,
+    pub(crate) theme: Box<dyn Theme>,
+    pub(crate) metadata: Metadata,
+    /// The position of the port to draw attention to, dimming all others
+    pub(crate) highlight: Option<usize>,
+}
+
+impl From<SwitchInput> for Switch {
+    fn from(input: SwitchInput) -> Self {
+        Switch {
+            ports: input.ports,
+            theme: input.theme.build(),
+            metadata: input.metadata,
+            highlight: None,
+        }
+    }
+}
+
+impl Switch {
+    /// Opens a switch file, in the given format or otherwise the one matching its extension.
+    pub fn open(path: impl AsRef<Path>, format: Option<Format>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let format = format.unwrap_or(Format::detect(path));
+        let source =
+            read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+        let input: SwitchInput = format
+            .parse(&source)
+            .with_context(|| format!("Could not parse {} as {}", path.display(), format))?;
+        input.validate().map_err(|error| anyhow!(error))?;
+        Ok(input.into())
+    }
+
+    /// The position of a numbered port, counting all ports from the top left.
+    pub(crate) fn position(&self, number: usize) -> Option<usize> {
+        let mut count = 0;
+        self.ports.iter().position(|port| {
+            count += port.kind.numbered() as usize;
+            port.kind.numbered() && count == number
+        })
+    }
+
+    /// Draws attention to the port at a position, and dims all others.
+    pub(crate) fn set_highlight(&mut self, position: usize) {
+        self.highlight = Some(position);
+    }
+
+    /// How much attention the balloon and art of a port draw.
+    pub(crate) fn emphasis(&self, position: usize) -> Emphasis {
+        match self.highlight {
+            Some(highlight) if highlight == position => Emphasis::Highlighted,
+            Some(_) => Emphasis::Muted,
+            None => Emphasis::Normal,
+        }
+    }
+
+    pub fn render(&mut self) -> String {
+        let (above, front, below) = self.render_layers();
+        let mut rendered = format!("{}\n{}{}", above, front, below);
+        if let Some(header) = self.metadata.header() {
+            rendered = format!("\n{}{}", header, rendered);
+        }
+        rendered
+    }
+
+    /// The text on a port, four characters wide: its number, or what kind of port it is.
+    pub(crate) fn port_name(&self, position: usize) -> String {
+        let port = &self.ports[position];
+        match port.kind {
+            PortKind::Console => "CON ".into(),
+            PortKind::Management => "MGMT".into(),
+            _ => {
+                let number = self.ports[..=position]
+                    .iter()
+                    .filter(|port| port.kind.numbered())
+                    .count();
+                format!("{:^4}", format!("{:02}", number))
+            }
+        }
+    }
+
+    /// The top ports that have a balloon, by name, with the text of their balloon.
+    pub(crate) fn top_hosts(&self) -> Vec<String> {
+        self.ports
+            .iter()
+            .enumerate()
+            .step_by(2)
+            .filter_map(|(position, port)| {
+                let name = self.port_name(position);
+                port.text().map(|text| format!("{} {}", name.trim(), text))
+            })
+            .collect()
+    }
+
+    /// Renders the balloons of the top ports, the front of the switch and the balloons of the
+    /// bottom ports, which hang upside down.
+    pub(crate) fn render_layers(&mut self) -> (String, String, String) {
+        let (mut top, mut bottom) = (Vec::new(), Vec::new());
+        for (position, port) in self.ports.iter().enumerate() {
+            let Some(text) = port.text() else {
+                continue;
+            };
+            let color = match port.key() {
+                Some(key) => self.theme.style_group(key),
+                None => Color::NEUTRAL,
+            };
+            let style = Style {
+                color,
+                tone: Tone::Firm,
+                emphasis: self.emphasis(position),
+            };
+            let balloon = (position / 2, text, style, Pin::default());
+            match position % 2 {
+                0 => top.push(balloon),
+                _ => bottom.push(balloon),
+            }
+        }
+        (
+            arrange(top, Shape::Boxed).render(&*self.theme),
+            self.theme.render_switch(self),
+            flip(&arrange(bottom, Shape::Boxed).render(&*self.theme)),
+        )
+    }
+}
+
+/// Turns rendered balloons upside down, so their arrows point up.
+fn flip(rendered: &str) -> String {
+    rendered
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .rev()
+        .map(|line| {
+            let line: String = line
+                .chars()
+                .map(|c| match c {
+                    '┌' => '└',
+                    '└' => '┌',
+                    '┐' => '┘',
+                    '┘' => '┐',
+                    '┬' => '┴',
+                    '┴' => '┬',
+                    c => c,
+                })
+                .collect();
+            format!("\n{}", line)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::switch::{flip, Switch, SwitchInput};
+
+    #[test]
+    fn test_port_name() {
+        let switch: Switch = serde_yaml::from_str::<SwitchInput>(indoc! {"
+            theme: ASCII
+            ports:
+              - kind: Console
+              - kind: Management
+              - label: NAS
+              - vlan: '20'
+              - kind: Sfp
+        "})
+        .unwrap()
+        .into();
+        assert_eq!(
+            vec!["CON ", "MGMT", " 01 ", " 02 ", " 03 "],
+            (0..5)
+                .map(|position| switch.port_name(position))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some("VLAN 20".into()), switch.ports[3].text());
+        assert_eq!(Some(4), switch.position(3));
+        assert_eq!(None, switch.position(4));
+        assert_eq!(vec!["01 NAS"], switch.top_hosts());
+    }
+
+    #[test]
+    fn test_flip() {
+        assert_eq!(
+            "\n  ││\n┌─┘└┐\n│ab │\n└───┘",
+            flip("\n┌───┐\n│ab │\n└─┐┌┘\n  ││")
+        );
+    }
+}
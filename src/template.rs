@@ -20,7 +20,7 @@ impl Template {
         Template { grid }
     }
 
-    pub fn render(self, theme: &Box<dyn Theme>) -> String {
+    pub fn render(self, theme: &dyn Theme) -> String {
         let mut string = String::new();
         for row in 0..self.grid.rows() {
             string.push('\n');
@@ -31,6 +31,19 @@ impl Template {
         string
     }
 
+    /// Writes the code of every placeholder, a line per row, see [`PlaceHolder::code`].
+    pub(crate) fn dump(&self) -> String {
+        (0..self.grid.rows())
+            .map(|row| {
+                self.grid
+                    .iter_row(row)
+                    .map(PlaceHolder::code)
+                    .collect::<String>()
+            })
+            .intersperse("\n".into())
+            .collect()
+    }
+
     pub(crate) fn overlay(self, another: Template) -> Self {
         let (one_grid, another_grid) = (self.grid, another.grid);
         let (longer, shorter) = if one_grid.rows() > another_grid.rows() {
@@ -71,7 +84,7 @@ impl Template {
 #[cfg(test)]
 mod tests {
     use crate::balloon::{max_shift, Balloon};
-    use crate::placeholder::Color;
+    use crate::placeholder::{Color, PlaceHolder, Style};
     use crate::template::Template;
 
     use crate::theme::GenericTheme;
@@ -84,7 +97,7 @@ mod tests {
 
     #[test]
     fn test_render() {
-        let theme = GenericTheme::ASCII.new();
+        let theme = GenericTheme::ASCII.build();
         let text = "Paarden";
         for slot in 0..24 {
             for shift in 0..max_shift(text, slot) {
@@ -93,7 +106,7 @@ mod tests {
                     Balloon::new(COLOR, text.into(), slot, 0, shift)
                         .unwrap()
                         .pre_render()
-                        .render(&theme)
+                        .render(&*theme)
                 );
             }
         }
@@ -102,13 +115,13 @@ mod tests {
             Balloon::new(COLOR, "Ferkels".into(), 4, 1, 1)
                 .unwrap()
                 .pre_render()
-                .render(&theme)
+                .render(&*theme)
         );
     }
 
     #[test]
     fn test_overlay() {
-        let theme = GenericTheme::ASCII.new();
+        let theme = GenericTheme::ASCII.build();
         println!(
             "{}",
             Template::_overlay_all(
@@ -116,26 +129,49 @@ mod tests {
                     Balloon::new(COLOR, "Paarden".into(), 0, 0, 1),
                     Balloon::new(COLOR, "Ferkels".into(), 2, 1, 3),
                     Balloon::new(COLOR, "Johans".into(), 3, 2, 2),
-                    Balloon::_right(COLOR, "Bacon is good for me".into(), 4, 0),
+                    Balloon::right(COLOR, "Bacon is good for me".into(), 4, 0),
                     Balloon::new(COLOR, "Lorum ipsum jonge".into(), 5, 1, 1),
                 ]
                 .into_iter()
                 .map(|balloon| balloon.unwrap().pre_render())
                 .collect()
             )
-            .render(&theme),
+            .render(&*theme),
         );
         println!(
             "{}",
-            Balloon::_right(COLOR, "Gekkenhuis joh hier".into(), 2, 0)
+            Balloon::right(COLOR, "Gekkenhuis joh hier".into(), 2, 0)
                 .unwrap()
                 .pre_render()
                 .overlay(
-                    Balloon::_right(COLOR, "In petersburg is poardenmarkt".into(), 3, 1)
+                    Balloon::right(COLOR, "In petersburg is poardenmarkt".into(), 3, 1)
                         .unwrap()
                         .pre_render()
                 )
-                .render(&theme)
+                .render(&*theme)
+        );
+    }
+
+    #[test]
+    fn test_overlay_style() {
+        let border: Style = COLOR.into();
+        let arrow: Style = Color {
+            red: 255,
+            green: 0,
+            blue: 0,
+        }
+        .into();
+        assert_eq!(
+            PlaceHolder::ArrowOverlayNorthLeft(arrow),
+            PlaceHolder::North(border).overlay(&PlaceHolder::ArrowLeft(arrow))
+        );
+        assert_eq!(
+            PlaceHolder::ArrowOverlaySouthRight(arrow),
+            PlaceHolder::ArrowRight(arrow).overlay(&PlaceHolder::South(border))
+        );
+        assert_eq!(
+            PlaceHolder::East(border),
+            PlaceHolder::ArrowLeft(arrow).overlay(&PlaceHolder::East(border))
         );
     }
 }
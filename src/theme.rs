@@ -1,9 +1,11 @@
+use crate::legend::Entry;
 use crate::panel::Panel;
-use crate::placeholder::{Color, PlaceHolder};
+use crate::placeholder::{Color, Emphasis, PlaceHolder, Tone};
 use crate::slot::Slot;
+use crate::switch::{PortKind, Switch};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::str::FromStr;
 
 trait AppendMultiline {
     fn append_multiline(&self, other: String) -> String;
@@ -25,14 +27,141 @@ impl AppendMultiline for String {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+/// Colours the text if there is a colour, like legend entries of groups.
+fn styled(text: &str, color: Option<Color>) -> String {
+    match color {
+        Some(color) => text.color(colored::Color::from(color)).to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// Draws a character of a balloon in the style of that balloon. Text always has the colour of
+/// the balloon, its outline and arrow only when asked for.
+fn paint(glyph: char, placeholder: PlaceHolder, colored_outlines: bool) -> String {
+    let Some(style) = placeholder.style() else {
+        return glyph.to_string();
+    };
+    let mut text = glyph.to_string().normal();
+    if let PlaceHolder::Text(..) = placeholder {
+        text = text.color(colored::Color::from(style.color));
+        if let Tone::Tentative = style.tone {
+            text = text.dimmed().italic();
+        }
+    } else if colored_outlines {
+        text = text.color(colored::Color::from(style.color));
+    }
+    match style.emphasis {
+        Emphasis::Normal => {}
+        Emphasis::Muted => text = text.dimmed(),
+        Emphasis::Highlighted => text = text.bold(),
+    }
+    text.to_string()
+}
+
+/// Splits a line into the characters it shows, each with the escape sequences right before it,
+/// and the escape sequences after the last one.
+fn cells(line: &str) -> (Vec<String>, String) {
+    let mut cells = Vec::new();
+    let mut pending = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        pending.push(c);
+        if c == '\x1b' {
+            // Up to the letter that ends the sequence
+            for c in chars.by_ref() {
+                pending.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            cells.push(std::mem::take(&mut pending));
+        }
+    }
+    (cells, pending)
+}
+
+/// The columns a line takes on the terminal, without its escape sequences.
+pub(crate) fn visible_width(line: &str) -> usize {
+    cells(line).0.len()
+}
+
+/// Centres a line of text under a drawing.
+fn centered(text: &str, drawing: &str) -> String {
+    let width = drawing.lines().map(visible_width).max().unwrap_or_default();
+    let padding = width.saturating_sub(text.chars().count()) / 2;
+    format!("{}{}", " ".repeat(padding), text)
+}
+
+/// Writes texts into an empty line of a frame, if they fit between the edges: a single text
+/// centred, or two at either end.
+fn embed(frame: &str, line: usize, texts: &[&str]) -> Option<String> {
+    let (mut row, tail) = cells(frame.lines().nth(line)?);
+    let inner = row.len().checked_sub(4)?;
+    if row[2..row.len() - 2]
+        .iter()
+        .any(|cell| !cell.ends_with(' '))
+    {
+        return None;
+    }
+    let length = |text: &str| text.chars().count();
+    let text = match texts {
+        [text] if length(text) <= inner => {
+            format!("{}{}", " ".repeat((inner - length(text)) / 2), text)
+        }
+        [left, right] if length(left) + length(right) + 3 <= inner => format!(
+            " {}{}{}",
+            left,
+            " ".repeat(inner - length(left) - length(right) - 2),
+            right
+        ),
+        _ => return None,
+    };
+    for (cell, c) in row[2..].iter_mut().zip(text.chars()) {
+        cell.pop();
+        cell.push(c);
+    }
+    let row = row.concat() + &tail;
+    Some(
+        frame
+            .lines()
+            .enumerate()
+            .map(|(index, original)| {
+                if index == line {
+                    row.as_str()
+                } else {
+                    original
+                }
+            })
+            .intersperse("\n")
+            .collect(),
+    )
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub enum GenericTheme {
+    #[default]
     ASCII,
     Rounded,
 }
 
+impl FromStr for GenericTheme {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "ascii" => Ok(GenericTheme::ASCII),
+            "rounded" => Ok(GenericTheme::Rounded),
+            _ => Err(format!(
+                "Unknown theme '{}', expected ascii or rounded",
+                string
+            )),
+        }
+    }
+}
+
 impl GenericTheme {
-    pub(crate) fn new(&self) -> Box<dyn Theme> {
+    pub(crate) fn build(&self) -> Box<dyn Theme> {
         match self {
             GenericTheme::ASCII => Box::new(DefaultTheme::new()),
             GenericTheme::Rounded => Box::new(RoundedTheme::new()),
@@ -43,16 +172,29 @@ impl GenericTheme {
 pub(crate) trait Theme {
     fn render(&self, placeholder: PlaceHolder) -> String;
 
-    fn render_slot(&self, slot: &Slot, index: usize) -> String;
+    fn render_slot(&self, slot: &Slot, index: usize, emphasis: Emphasis) -> String;
+
+    /// The frame of the panel, and the header of its metadata if that has to go above the
+    /// balloons instead.
+    fn render_panel(&self, panel: &Panel) -> (String, Option<String>);
 
-    fn render_panel(&self, panel: &Panel) -> String;
+    fn render_legend(&self, entries: &[Entry]) -> String;
+
+    fn render_switch(&self, switch: &Switch) -> String;
 
     fn style_group(&mut self, group: &str) -> Color;
+
+    /// Draws the outlines and arrows of balloons in their colour, not just their text.
+    fn color_outlines(&mut self, colored: bool);
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct DefaultTheme {
-    groups: HashSet<String>,
+    /// The keys that were handed a colour, in order
+    groups: Vec<String>,
+    /// See [`Theme::color_outlines`]
+    #[serde(default)]
+    colored_outlines: bool,
 }
 
 impl Default for DefaultTheme {
@@ -66,80 +208,222 @@ impl DefaultTheme {
     const RIGHT: &'static str = include_str!("../resources/default/right.txt");
     const SEPARATOR: &'static str = include_str!("../resources/default/separator.txt");
     const SLOT: &'static str = include_str!("../resources/default/slot.txt");
+    const SWITCH_LEFT: &'static str = include_str!("../resources/default/switch/left.txt");
+    const SWITCH_RIGHT: &'static str = include_str!("../resources/default/switch/right.txt");
+    const SWITCH_SEPARATOR: &'static str =
+        include_str!("../resources/default/switch/separator.txt");
+    const SWITCH_COLUMN: &'static str = include_str!("../resources/default/switch/column.txt");
+    const COPPER: &'static str = include_str!("../resources/default/switch/copper.txt");
+    const SFP: &'static str = include_str!("../resources/default/switch/sfp.txt");
+    const CONSOLE: &'static str = include_str!("../resources/default/switch/console.txt");
+    const MANAGEMENT: &'static str = include_str!("../resources/default/switch/management.txt");
+
+    /// The lines of the slot template with the top and bottom of the jack
+    const JACK_BORDERS: [usize; 2] = [2, 6];
 
     pub fn new() -> Self {
         Self {
-            groups: HashSet::new(),
+            groups: Vec::new(),
+            colored_outlines: false,
         }
     }
 }
 
+impl DefaultTheme {
+    /// Draws a column of the switch, with a port on top and one below.
+    fn render_switch_column(&self, switch: &Switch, column: usize) -> String {
+        let mut base = "\n".repeat(Self::SWITCH_LEFT.lines().count());
+        if column != 0 {
+            base = Self::SWITCH_SEPARATOR.into();
+        }
+        let port = |position: usize| -> (Vec<String>, bool) {
+            match switch.ports.get(position) {
+                None => (vec![" ".repeat(6); 3], false),
+                Some(port) => {
+                    let art = match port.kind {
+                        PortKind::Copper => Self::COPPER,
+                        PortKind::Sfp => Self::SFP,
+                        PortKind::Console => Self::CONSOLE,
+                        PortKind::Management => Self::MANAGEMENT,
+                    };
+                    let emphasis = switch.emphasis(position);
+                    let art = art.replace("<nn>", &switch.port_name(position));
+                    // Like the jacks of a panel, highlighted copper ports get doubled borders
+                    let art = match (port.kind, emphasis) {
+                        (PortKind::Copper, Emphasis::Highlighted) => art
+                            .replace('─', "═")
+                            .replace('┌', "╒")
+                            .replace('┐', "╕")
+                            .replace('└', "╘")
+                            .replace('┘', "╛"),
+                        _ => art,
+                    };
+                    (
+                        art.lines()
+                            .map(|line| match emphasis {
+                                Emphasis::Normal => line.to_string(),
+                                Emphasis::Muted => line.dimmed().to_string(),
+                                Emphasis::Highlighted => line.bold().to_string(),
+                            })
+                            .collect(),
+                        port.text().is_some(),
+                    )
+                }
+            }
+        };
+        let ((top, top_cable), (bottom, bottom_cable)) = (port(column * 2), port(column * 2 + 1));
+        let connector = |cabled: bool| if cabled { "┤├" } else { "──" };
+        let cable = |cabled: bool| if cabled { "││" } else { "  " };
+        base.append_multiline(
+            Self::SWITCH_COLUMN
+                .replace("<topconnector>", connector(top_cable))
+                .replace("<topcable>", cable(top_cable))
+                .replace("<top0>", &top[0])
+                .replace("<top1>", &top[1])
+                .replace("<top2>", &top[2])
+                .replace("<bottom0>", &bottom[0])
+                .replace("<bottom1>", &bottom[1])
+                .replace("<bottom2>", &bottom[2])
+                .replace("<bottomcable>", cable(bottom_cable))
+                .replace("<bottomconnector>", connector(bottom_cable)),
+        )
+    }
+}
+
 impl Theme for DefaultTheme {
     fn render(&self, placeholder: PlaceHolder) -> String {
-        match placeholder {
-            PlaceHolder::Text(c, color) => {
-                return c
-                    .to_string()
-                    .color(colored::Color::TrueColor {
-                        r: color.red,
-                        g: color.green,
-                        b: color.blue,
-                    })
-                    .to_string()
-            }
+        let glyph = match placeholder {
+            PlaceHolder::Text(c, _) => c,
             PlaceHolder::None | PlaceHolder::Padding => ' ',
-            PlaceHolder::North | PlaceHolder::South => '─',
-            PlaceHolder::East | PlaceHolder::West => '│',
-            PlaceHolder::NorthEast | PlaceHolder::TransitionLeft => '┐',
-            PlaceHolder::NorthWest | PlaceHolder::TransitionRight => '┌',
-            PlaceHolder::SouthEast => '┘',
-            PlaceHolder::SouthWest => '└',
-            PlaceHolder::TransitionLeftEdge | PlaceHolder::TransitionRightEdge => '│',
-            PlaceHolder::ArrowLeft | PlaceHolder::ArrowRight => '│',
-            PlaceHolder::ArrowOverlayNorthLeft | PlaceHolder::ArrowOverlayNorthRight => '┴',
-            PlaceHolder::ArrowOverlaySouthLeft | PlaceHolder::ArrowOverlaySouthRight => '┬',
-        }
-        .to_string()
+            PlaceHolder::North(_) | PlaceHolder::South(_) => '─',
+            PlaceHolder::East(_) | PlaceHolder::West(_) => '│',
+            PlaceHolder::NorthEast(_) | PlaceHolder::TransitionLeft(_) => '┐',
+            PlaceHolder::NorthWest(_) | PlaceHolder::TransitionRight(_) => '┌',
+            PlaceHolder::SouthEast(_) => '┘',
+            PlaceHolder::SouthWest(_) => '└',
+            PlaceHolder::TransitionLeftEdge(_) | PlaceHolder::TransitionRightEdge(_) => '│',
+            PlaceHolder::Leader(_) => '─',
+            PlaceHolder::LeaderJoint(_) => '┬',
+            PlaceHolder::ArrowLeft(style) | PlaceHolder::ArrowRight(style) => match style.tone {
+                Tone::Firm => '│',
+                Tone::Tentative => '╎',
+            },
+            // Arrows go on down to the panel, so they cross the balloons they run into
+            PlaceHolder::ArrowOverlayNorthLeft(_)
+            | PlaceHolder::ArrowOverlayNorthRight(_)
+            | PlaceHolder::ArrowOverlaySouthLeft(_)
+            | PlaceHolder::ArrowOverlaySouthRight(_) => '┼',
+        };
+        paint(glyph, placeholder, self.colored_outlines)
     }
 
-    fn render_slot(&self, slot: &Slot, index: usize) -> String {
+    fn render_slot(&self, slot: &Slot, index: usize, emphasis: Emphasis) -> String {
         let mut base = "\n".repeat(DefaultTheme::LEFT.lines().count());
         if index != 0 {
             base = Self::SEPARATOR.into();
         }
-        let charset = if let Slot::Free = slot {
-            [" ", " ", "─", "─", "─", "─"]
-        } else {
-            ["│", "│", "┤", "├", "┘", "└"]
+        let highlighted = emphasis == Emphasis::Highlighted;
+        let charset = match slot {
+            Slot::Free => [" ", " ", "─", "─", "─", "─"],
+            // Highlighted jacks keep a single cable, only their borders are doubled
+            Slot::Occupied { .. } if highlighted => ["│", "│", "┤", "├", "╛", "╘"],
+            Slot::Occupied { .. } => ["│", "│", "┤", "├", "┘", "└"],
+            Slot::Reserved { .. } if highlighted => ["╎", "╎", "┤", "├", "╛", "╘"],
+            Slot::Reserved { .. } => ["╎", "╎", "┤", "├", "┘", "└"],
+            // A planned cable isn't there yet, so it is drawn even more sparsely than a reserved one
+            Slot::Planned { .. } if highlighted => ["┆", "┆", "┤", "├", "╛", "╘"],
+            Slot::Planned { .. } => ["┆", "┆", "┤", "├", "┘", "└"],
+            Slot::Faulty => ["╳", "╳", "─", "─", "─", "─"],
+            Slot::Disabled => ["▒", "▒", "─", "─", "─", "─"],
         };
-        base.append_multiline(
-            Self::SLOT
-                .replace("<left>", charset[0])
-                .replace("<right>", charset[1])
-                .replace("<topleft>", charset[2])
-                .replace("<topright>", charset[3])
-                .replace("<bottomleft>", charset[4])
-                .replace("<bottomright>", charset[5])
-                .replace("<padding>", " ")
-                .replace("<slotindex>", format!("{:02}", index).as_str()),
-        )
+        let slot = Self::SLOT
+            .replace("<left>", charset[0])
+            .replace("<right>", charset[1])
+            .replace("<topleft>", charset[2])
+            .replace("<topright>", charset[3])
+            .replace("<bottomleft>", charset[4])
+            .replace("<bottomright>", charset[5])
+            .replace("<padding>", " ");
+        let number = format!("{:02}", index);
+        // Style line by line, the slot is glued to its neighbours one line at a time
+        let slot = slot
+            .lines()
+            .enumerate()
+            .map(|(row, line)| {
+                let mut line = line.replace("<slotindex>", &number);
+                if highlighted && Self::JACK_BORDERS.contains(&row) {
+                    line = line.replace('─', "═");
+                }
+                match emphasis {
+                    Emphasis::Normal => line,
+                    Emphasis::Muted => line.dimmed().to_string(),
+                    Emphasis::Highlighted => line.bold().to_string(),
+                }
+            })
+            .intersperse("\n".into())
+            .collect();
+        base.append_multiline(slot)
     }
 
-    fn render_panel(&self, panel: &Panel) -> String {
-        panel
+    fn render_panel(&self, panel: &Panel) -> (String, Option<String>) {
+        let frame = panel
             .slots
             .iter()
             .enumerate()
-            .map(|(index, slot)| self.render_slot(slot, index))
+            .map(|(index, slot)| self.render_slot(slot, index, panel.emphasis(index, slot)))
             .fold(Self::LEFT.into(), |left: String, slot| {
                 left.append_multiline(slot)
             })
-            .append_multiline(Self::RIGHT.into())
+            .append_multiline(Self::RIGHT.into());
+        let frame = match panel.metadata.footer() {
+            Some(footer) => format!("{}\n{}", frame, centered(&footer, &frame)),
+            None => frame,
+        };
+        (frame, panel.metadata.header())
+    }
+
+    fn render_switch(&self, switch: &Switch) -> String {
+        (0..switch.ports.len().div_ceil(2))
+            .map(|column| self.render_switch_column(switch, column))
+            .fold(Self::SWITCH_LEFT.into(), |left: String, column| {
+                left.append_multiline(column)
+            })
+            .append_multiline(Self::SWITCH_RIGHT.into())
+    }
+
+    fn render_legend(&self, entries: &[Entry]) -> String {
+        let width = entries
+            .iter()
+            .map(|entry| entry.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{}{}  {}",
+                    styled(&entry.name, entry.color),
+                    " ".repeat(width - entry.name.chars().count()),
+                    entry.details()
+                )
+            })
+            .intersperse("\n".into())
+            .collect()
+    }
+
+    fn color_outlines(&mut self, colored: bool) {
+        self.colored_outlines = colored;
     }
 
     fn style_group(&mut self, group: &str) -> Color {
-        self.groups.insert(group.to_string());
-        match self.groups.len() - 1 {
+        let index = match self.groups.iter().position(|known| known == group) {
+            Some(index) => index,
+            None => {
+                self.groups.push(group.to_string());
+                self.groups.len() - 1
+            }
+        };
+        match index {
             0 => Color {
                 red: 255,
                 green: 0,
@@ -205,6 +489,11 @@ impl RoundedTheme {
 
     const RIGHT: &'static str = include_str!("../resources/rounded/right.txt");
 
+    const LEGEND: &'static str = " Legend ";
+
+    /// The line of the frame between the ears and the bottom edge, for the header and footer
+    const LABEL_LINE: usize = 7;
+
     pub fn new() -> Self {
         Self {
             delegate: DefaultTheme::new(),
@@ -215,56 +504,129 @@ impl RoundedTheme {
 /// This one doesn't seem to work on Linux terminals unfortunately
 impl Theme for RoundedTheme {
     fn render(&self, placeholder: PlaceHolder) -> String {
-        match placeholder {
-            PlaceHolder::Text(c, color) => {
-                return c
-                    .to_string()
-                    .color(colored::Color::TrueColor {
-                        r: color.red,
-                        g: color.green,
-                        b: color.blue,
-                    })
-                    .to_string()
-            }
+        let glyph = match placeholder {
+            PlaceHolder::Text(c, _) => c,
             PlaceHolder::None | PlaceHolder::Padding => ' ',
-            PlaceHolder::North | PlaceHolder::South => '─',
-            PlaceHolder::East | PlaceHolder::West => '│',
-            PlaceHolder::NorthEast | PlaceHolder::TransitionLeft => '┐',
-            PlaceHolder::NorthWest | PlaceHolder::TransitionRight => '┌',
-            PlaceHolder::SouthEast => '┘',
-            PlaceHolder::SouthWest => '└',
-            PlaceHolder::TransitionLeftEdge | PlaceHolder::TransitionRightEdge => '│',
-            PlaceHolder::ArrowLeft | PlaceHolder::ArrowRight => '│',
-            PlaceHolder::ArrowOverlayNorthLeft | PlaceHolder::ArrowOverlayNorthRight => '┴',
-            PlaceHolder::ArrowOverlaySouthLeft | PlaceHolder::ArrowOverlaySouthRight => '┬',
-        }
-        .to_string()
+            PlaceHolder::North(_) | PlaceHolder::South(_) => '─',
+            PlaceHolder::East(_) | PlaceHolder::West(_) => '│',
+            PlaceHolder::NorthEast(_) | PlaceHolder::TransitionLeft(_) => '┐',
+            PlaceHolder::NorthWest(_) | PlaceHolder::TransitionRight(_) => '┌',
+            PlaceHolder::SouthEast(_) => '┘',
+            PlaceHolder::SouthWest(_) => '└',
+            PlaceHolder::TransitionLeftEdge(_) | PlaceHolder::TransitionRightEdge(_) => '│',
+            PlaceHolder::Leader(_) => '─',
+            PlaceHolder::LeaderJoint(_) => '┬',
+            PlaceHolder::ArrowLeft(style) | PlaceHolder::ArrowRight(style) => match style.tone {
+                Tone::Firm => '│',
+                Tone::Tentative => '╎',
+            },
+            // Arrows go on down to the panel, so they cross the balloons they run into
+            PlaceHolder::ArrowOverlayNorthLeft(_)
+            | PlaceHolder::ArrowOverlayNorthRight(_)
+            | PlaceHolder::ArrowOverlaySouthLeft(_)
+            | PlaceHolder::ArrowOverlaySouthRight(_) => '┼',
+        };
+        paint(glyph, placeholder, self.delegate.colored_outlines)
     }
 
-    fn render_slot(&self, slot: &Slot, index: usize) -> String {
-        self.delegate.render_slot(slot, index)
+    fn render_slot(&self, slot: &Slot, index: usize, emphasis: Emphasis) -> String {
+        self.delegate.render_slot(slot, index, emphasis)
     }
 
-    fn render_panel(&self, panel: &Panel) -> String {
-        panel
+    fn render_switch(&self, switch: &Switch) -> String {
+        self.delegate.render_switch(switch)
+    }
+
+    fn render_panel(&self, panel: &Panel) -> (String, Option<String>) {
+        let frame = panel
             .slots
             .iter()
             .enumerate()
-            .map(|(index, slot)| self.render_slot(slot, index))
+            .map(|(index, slot)| self.render_slot(slot, index, panel.emphasis(index, slot)))
             .fold(Self::LEFT.into(), |left: String, slot| {
                 left.append_multiline(slot)
             })
-            .append_multiline(Self::RIGHT.into())
+            .append_multiline(Self::RIGHT.into());
+        // Inside the frame, on the empty line below the ears: the header on the left and the
+        // footer on the right, or whichever of them fits
+        let (header, footer) = (panel.metadata.header(), panel.metadata.footer());
+        if let (Some(header), Some(footer)) = (&header, &footer) {
+            if let Some(framed) = embed(&frame, Self::LABEL_LINE, &[header, footer]) {
+                return (framed, None);
+            }
+        }
+        match (header, footer) {
+            (header, Some(footer)) => match embed(&frame, Self::LABEL_LINE, &[&footer]) {
+                Some(framed) => (framed, header),
+                None => (format!("{}\n{}", frame, centered(&footer, &frame)), header),
+            },
+            (Some(header), None) => match embed(&frame, Self::LABEL_LINE, &[&header]) {
+                Some(framed) => (framed, None),
+                None => (frame, Some(header)),
+            },
+            (None, None) => (frame, None),
+        }
+    }
+
+    fn render_legend(&self, entries: &[Entry]) -> String {
+        let width = entries
+            .iter()
+            .map(|entry| entry.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        let lines: Vec<(String, usize)> = entries
+            .iter()
+            .map(|entry| {
+                let padding = " ".repeat(width - entry.name.chars().count());
+                let details = entry.details();
+                (
+                    format!(
+                        "{} {}{}  {}",
+                        styled(if entry.color.is_some() { "■" } else { " " }, entry.color),
+                        styled(&entry.name, entry.color),
+                        padding,
+                        details
+                    ),
+                    width + details.chars().count() + 4,
+                )
+            })
+            .collect();
+        let inner = lines
+            .iter()
+            .map(|(_, length)| *length)
+            .max()
+            .unwrap_or_default()
+            .max(Self::LEGEND.chars().count() + 1);
+        [format!(
+            "╭─{}{}─╮",
+            Self::LEGEND,
+            "─".repeat(inner - Self::LEGEND.chars().count())
+        )]
+        .into_iter()
+        .chain(
+            lines
+                .into_iter()
+                .map(|(line, length)| format!("│ {}{} │", line, " ".repeat(inner - length))),
+        )
+        .chain([format!("╰{}╯", "─".repeat(inner + 2))])
+        .intersperse("\n".into())
+        .collect()
     }
 
     fn style_group(&mut self, group: &str) -> Color {
         self.delegate.style_group(group)
     }
+
+    fn color_outlines(&mut self, colored: bool) {
+        self.delegate.color_outlines(colored)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::theme::AppendMultiline;
+    use crate::placeholder::Emphasis;
+    use crate::slot::Slot;
+    use crate::theme::{centered, embed, visible_width, AppendMultiline, DefaultTheme, Theme};
 
     #[test]
     fn test_multiline_append() {
@@ -272,4 +634,48 @@ mod tests {
         let right = "1\n2\n3";
         assert_eq!("a1\nb2\nc3", left.append_multiline(right.into()))
     }
+
+    #[test]
+    fn test_embed() {
+        let frame = "╭────────╮\n│        │\n╰────────╯";
+        assert_eq!(
+            Some("╭────────╮\n│  Jan   │\n╰────────╯".into()),
+            embed(frame, 1, &["Jan"])
+        );
+        assert_eq!(None, embed(frame, 1, &["Too long"]));
+        assert_eq!(None, embed(frame, 0, &["Jan"]));
+        assert_eq!(
+            Some("╭────────╮\n│  A  B  │\n╰────────╯".into()),
+            embed(frame, 1, &["A", "B"])
+        );
+        assert_eq!(None, embed(frame, 1, &["Anne", "Bo"]));
+
+        // Escape sequences take no room
+        let dimmed = "│\x1b[2m        \x1b[0m│";
+        assert_eq!(
+            Some("│\x1b[2m  Jan   \x1b[0m│".into()),
+            embed(dimmed, 0, &["Jan"])
+        );
+        assert_eq!("   Jan", centered("Jan", dimmed));
+        assert_eq!(10, visible_width(dimmed));
+    }
+
+    #[test]
+    fn test_slot_states() {
+        let theme = DefaultTheme::new();
+        let reserved = Slot::Reserved {
+            owner: "Anne".into(),
+            expiry: None,
+            pin: None,
+        };
+        let planned = Slot::Planned {
+            text: "Printer".into(),
+            group: "Office".into(),
+            pin: None,
+        };
+        let reserved = theme.render_slot(&reserved, 0, Emphasis::Normal);
+        let planned = theme.render_slot(&planned, 0, Emphasis::Normal);
+        assert!(reserved.contains('╎') && !reserved.contains('┆'));
+        assert!(planned.contains('┆') && !planned.contains('╎'));
+    }
 }
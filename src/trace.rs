@@ -0,0 +1,372 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::crossconnect::{Cord, CrossConnect};
+use crate::edit::Edit;
+use crate::filter::Highlight;
+use crate::format::Format;
+use crate::inventory::Inventory;
+use crate::panel::Panel;
+use crate::switch::Switch;
+
+/// A wall outlet, wired to the rear of a port of the panel.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Outlet {
+    pub(crate) port: usize,
+    /// Where the outlet is, like `Bedroom 2`
+    pub(crate) name: String,
+}
+
+/// Where the jacks of a panel lead: outlets wired to their rear, and patch cords from their front
+/// to a switch.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Wiring {
+    /// The switch file the cords go to, relative to the inventory file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) switch: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) outlets: Vec<Outlet>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) cords: Vec<Cord>,
+}
+
+impl Wiring {
+    pub fn is_empty(&self) -> bool {
+        self == &Wiring::default()
+    }
+
+    /// Checks that the outlets go to ports of a panel with this many ports. The cords are checked
+    /// against the switch once it is loaded.
+    pub(crate) fn validate(&self, ports: usize) -> Result<(), String> {
+        let mut problems = Vec::new();
+        for (index, outlet) in self.outlets.iter().enumerate() {
+            if outlet.port >= ports {
+                problems.push(format!(
+                    "Outlet {} is wired to port {:02}, which doesn't exist",
+                    outlet.name, outlet.port
+                ));
+            }
+            if self.outlets[..index]
+                .iter()
+                .any(|other| other.port == outlet.port)
+            {
+                problems.push(format!("Port {:02} has two outlets", outlet.port));
+            }
+        }
+        for (index, cord) in self.cords.iter().enumerate() {
+            if self.cords[..index]
+                .iter()
+                .any(|other| other.panel == cord.panel)
+            {
+                problems.push(format!("Port {:02} has two cords", cord.panel));
+            }
+        }
+        if !self.cords.is_empty() && self.switch.is_none() {
+            problems.push("Cords need a switch to go to".into());
+        }
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems.join("\n")),
+        }
+    }
+
+    /// Checks that an edit of the slots leaves the wiring right. The wiring stays with its port,
+    /// so moving or swapping a wired port would leave its outlet and cord behind, and a free port
+    /// can't have a cord.
+    pub(crate) fn check(&self, edit: &Edit) -> Result<(), String> {
+        let outlet = |port: usize| self.outlets.iter().find(|outlet| outlet.port == port);
+        let cord = |port: usize| self.cords.iter().any(|cord| cord.panel == port);
+        match edit {
+            Edit::Move { .. } | Edit::Swap { .. } => {
+                for port in edit.ports() {
+                    if let Some(outlet) = outlet(port) {
+                        return Err(format!(
+                            "Port {:02} is wired to outlet {}, change the wiring first",
+                            port, outlet.name
+                        ));
+                    }
+                    if cord(port) {
+                        return Err(format!(
+                            "Port {:02} has a cord to the switch, change the wiring first",
+                            port
+                        ));
+                    }
+                }
+            }
+            Edit::Free { port } if cord(*port) => {
+                return Err(format!(
+                    "Port {:02} has a cord to the switch, remove it from the wiring first",
+                    port
+                ));
+            }
+            Edit::Set { .. } | Edit::Free { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// The path from a wall outlet through a port of the panel and a patch cord to a switch.
+pub struct Trace {
+    panel: Panel,
+    switch: Option<Switch>,
+    port: usize,
+    outlet: Option<String>,
+    cord: Option<Cord>,
+}
+
+impl Trace {
+    /// Opens an inventory and the switch it is wired to, and finds the port a query is about.
+    pub fn open(
+        path: impl AsRef<Path>,
+        format: Option<Format>,
+        query: &str,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let inventory = Inventory::open(path, format)?;
+        let wiring = inventory.wiring().clone();
+        let panel = inventory.panel();
+        wiring
+            .validate(panel.slots.len())
+            .map_err(|error| anyhow!(error))?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let switch = match &wiring.switch {
+            Some(file) => Some(Switch::open(directory.join(file), None)?),
+            None => None,
+        };
+        let port = find(&panel, &wiring, switch.as_ref(), query).map_err(|error| anyhow!(error))?;
+        Ok(Self {
+            outlet: wiring
+                .outlets
+                .iter()
+                .find(|outlet| outlet.port == port)
+                .map(|outlet| outlet.name.clone()),
+            cord: wiring.cords.into_iter().find(|cord| cord.panel == port),
+            panel,
+            switch,
+            port,
+        })
+    }
+
+    /// Changes the panel, like to apply view options to it.
+    pub fn map_panel(
+        mut self,
+        change: impl Fn(Panel) -> anyhow::Result<Panel>,
+    ) -> anyhow::Result<Self> {
+        self.panel = change(self.panel)?;
+        Ok(self)
+    }
+
+    /// Every step of the path, as a kind and what it is.
+    pub(crate) fn path(&self) -> Vec<(&'static str, String)> {
+        let mut path = Vec::new();
+        if let Some(outlet) = &self.outlet {
+            path.push(("Outlet", outlet.clone()));
+        }
+        let slot = &self.panel.slots[self.port];
+        let panel = match &self.panel.metadata.name {
+            Some(name) => format!("{}, port {:02}", name, self.port),
+            None => format!("Port {:02}", self.port),
+        };
+        path.push(match slot.label() {
+            Some(label) => ("Panel", format!("{} ({})", panel, label)),
+            None => ("Panel", format!("{} ({})", panel, slot.state())),
+        });
+        let (Some(cord), Some(switch)) = (&self.cord, &self.switch) else {
+            path.push(("Cord", "None".into()));
+            return path;
+        };
+        path.push((
+            "Cord",
+            cord.color.clone().unwrap_or_else(|| "Patch cord".into()),
+        ));
+        let port = switch
+            .position(cord.switch)
+            .map(|position| &switch.ports[position]);
+        let name = match &switch.metadata.name {
+            Some(name) => format!("{}, port {:02}", name, cord.switch),
+            None => format!("Port {:02}", cord.switch),
+        };
+        path.push(("Switch", name));
+        if let Some(vlan) = port.and_then(|port| port.vlan.clone()) {
+            path.push(("VLAN", vlan));
+        }
+        if let Some(host) = port.and_then(|port| port.label.clone()) {
+            path.push(("Host", host));
+        }
+        path
+    }
+
+    /// Lists the path, and draws the panel and the switch with the path highlighted.
+    pub fn render(mut self) -> anyhow::Result<String> {
+        let path = self.path();
+        let width = path.iter().map(|(kind, _)| kind.len()).max().unwrap_or(0);
+        let listing: String = path
+            .iter()
+            .map(|(kind, step)| format!("{:width$}  {}", kind, step, width = width))
+            .join("\n");
+
+        self.panel
+            .set_highlight(Highlight::Ports(vec![self.port]))
+            .expect("The traced port is on the panel");
+        let drawing = match (self.switch, self.cord) {
+            (Some(mut switch), Some(cord)) => {
+                if let Some(position) = switch.position(cord.switch) {
+                    switch.set_highlight(position);
+                }
+                CrossConnect::new(self.panel, switch, vec![cord])
+                    .map_err(|error| anyhow!(error))?
+                    .render()?
+            }
+            _ => self.panel.render(),
+        };
+        Ok(format!("{}\n{}", listing, drawing))
+    }
+}
+
+/// The port a query is about: a port number, or the label of a port, its outlet or the host at
+/// the other end of its cord, ignoring case.
+fn find(
+    panel: &Panel,
+    wiring: &Wiring,
+    switch: Option<&Switch>,
+    query: &str,
+) -> Result<usize, String> {
+    if let Ok(port) = query.parse::<usize>() {
+        return match port < panel.slots.len() {
+            true => Ok(port),
+            false => Err(format!("Port {:02} doesn't exist", port)),
+        };
+    }
+    let matches = |name: &str| name.eq_ignore_ascii_case(query);
+    let labels = panel
+        .slots
+        .iter()
+        .positions(|slot| slot.label().is_some_and(matches));
+    let outlets = wiring
+        .outlets
+        .iter()
+        .filter(|outlet| matches(&outlet.name))
+        .map(|outlet| outlet.port);
+    let hosts = wiring
+        .cords
+        .iter()
+        .filter(|cord| {
+            switch
+                .and_then(|switch| switch.position(cord.switch).map(|p| &switch.ports[p]))
+                .and_then(|port| port.label.as_deref())
+                .is_some_and(matches)
+        })
+        .map(|cord| cord.panel);
+    let ports: Vec<usize> = labels
+        .chain(outlets)
+        .chain(hosts)
+        .sorted()
+        .dedup()
+        .collect();
+    match ports[..] {
+        [] => Err(format!("Nothing is called '{}'", query)),
+        [port] => Ok(port),
+        _ => Err(format!(
+            "'{}' could be any of ports {}",
+            query,
+            ports.iter().map(|port| format!("{:02}", port)).join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::edit::Edit;
+    use crate::panel::{Input, Panel};
+    use crate::switch::{Switch, SwitchInput};
+    use crate::trace::{find, Trace, Wiring};
+
+    #[test]
+    fn test_trace() {
+        let input: Input = serde_yaml::from_str(indoc! {"
+            theme: ASCII
+            metadata:
+              name: Panel A
+            wiring:
+              switch: switch.yaml
+              outlets:
+                - port: 1
+                  name: Bedroom
+              cords:
+                - panel: 1
+                  switch: 2
+                  color: blue
+            slots:
+              - !Occupied
+                text: Printer
+                group: Office
+              - !Occupied
+                text: Television
+                group: Bedroom
+        "})
+        .unwrap();
+        let switch: Switch = serde_yaml::from_str::<SwitchInput>(indoc! {"
+            theme: ASCII
+            ports:
+              - label: NAS
+              - label: Media
+                vlan: '20'
+        "})
+        .unwrap()
+        .into();
+        let wiring = input.wiring.clone();
+        let panel = Panel::from(input);
+
+        assert_eq!(Ok(1), find(&panel, &wiring, Some(&switch), "bedroom"));
+        assert_eq!(Ok(1), find(&panel, &wiring, Some(&switch), "Media"));
+        assert_eq!(Ok(0), find(&panel, &wiring, Some(&switch), "0"));
+        assert!(find(&panel, &wiring, Some(&switch), "Kitchen").is_err());
+
+        let trace = Trace {
+            panel,
+            switch: Some(switch),
+            port: 1,
+            outlet: Some("Bedroom".into()),
+            cord: wiring.cords.first().cloned(),
+        };
+        assert_eq!(
+            vec![
+                ("Outlet", "Bedroom".to_string()),
+                ("Panel", "Panel A, port 01 (Television)".into()),
+                ("Cord", "blue".into()),
+                ("Switch", "Port 02".into()),
+                ("VLAN", "20".into()),
+                ("Host", "Media".into()),
+            ],
+            trace.path()
+        );
+    }
+
+    #[test]
+    fn test_wiring() {
+        let wiring: Wiring = serde_yaml::from_str(indoc! {"
+            switch: switch.yaml
+            outlets:
+              - port: 1
+                name: Bedroom
+            cords:
+              - panel: 2
+                switch: 1
+              - panel: 2
+                switch: 3
+        "})
+        .unwrap();
+        assert_eq!(Err("Port 02 has two cords".into()), wiring.validate(4));
+
+        assert!(wiring.check(&Edit::Move { from: 1, to: 0 }).is_err());
+        assert!(wiring.check(&Edit::Swap { a: 3, b: 2 }).is_err());
+        assert!(wiring.check(&Edit::Swap { a: 0, b: 3 }).is_ok());
+        assert!(wiring.check(&Edit::Free { port: 2 }).is_err());
+        assert!(wiring.check(&Edit::Free { port: 1 }).is_ok());
+    }
+}